@@ -1,16 +1,27 @@
 //! Binary entry point for the subtitle extractor.
 
-use anyhow::Result;
-use clap::Parser;
-use std::path::PathBuf;
-use subtra_core::translate::{openai::OpenAiTranslator, process_file, DEFAULT_BATCH_SIZE};
-use subtra_core::video::extract_english_subtitles;
+use anyhow::{anyhow, Result};
+use clap::{Args, Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use subtra_core::locale::{self, DEFAULT_SOURCE_LANGUAGE, DEFAULT_TARGET_LOCALE};
+use subtra_core::translate::{
+    backend::{Backend, BackendConfig},
+    build_series_glossary,
+    memory::TranslationMemory,
+    process_file_multi, AsyncTranslator, ProcessOptions, DEFAULT_BATCH_SIZE,
+    DEFAULT_GLOSSARY_SAMPLE, DEFAULT_GLOSSARY_TOKEN_BUDGET,
+};
+use subtra_core::video::{self, extract_subtitles_with, StreamPolicy, StreamSelection};
 use tracing_subscriber::EnvFilter;
 
 /// Command line options for the binary.
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
-    /// When true we only extract the English subtitles from the input file.
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// When true we only extract the source language subtitles from the input file.
     #[arg(long)]
     onlyextract: bool,
 
@@ -18,12 +29,208 @@ struct Cli {
     #[arg(long)]
     debug: bool,
 
+    #[command(flatten)]
+    backend: BackendArgs,
+
     /// Number of subtitle lines to translate per batch.
     #[arg(long, default_value_t = DEFAULT_BATCH_SIZE)]
     batch_size: usize,
 
-    /// Path to the video or SRT file we want to process.
-    input: PathBuf,
+    /// Pack batches by estimated tokens of source text instead of line count, breaking at
+    /// scene changes; `--batch-size` still caps the lines per batch.
+    #[arg(long)]
+    token_budget: Option<usize>,
+
+    /// Number of batches to translate at the same time.
+    #[arg(long, default_value_t = 1)]
+    concurrency: usize,
+
+    /// Translation memory file reused across runs; defaults to
+    /// `subtra/translation_memory.jsonl` in the user's cache directory.
+    #[arg(long, conflicts_with = "no_cache")]
+    cache: Option<PathBuf>,
+
+    /// Send every line to the backend instead of reusing remembered translations.
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Glossary JSON file shared by a series: loaded, merged with the glossary built for
+    /// this file and written back (see `glossary build`).
+    #[arg(long)]
+    glossary: Option<PathBuf>,
+
+    /// Number of subtitle lines, spread over the runtime, the glossary is built from.
+    #[arg(long, default_value_t = DEFAULT_GLOSSARY_SAMPLE)]
+    glossary_sample: usize,
+
+    /// Estimated tokens of source text sent to build the glossary.
+    #[arg(long, default_value_t = DEFAULT_GLOSSARY_TOKEN_BUDGET)]
+    glossary_token_budget: usize,
+
+    #[command(flatten)]
+    source: SourceArgs,
+
+    /// Encoding of the translated files, e.g. `windows-1252` for players that cannot read UTF-8.
+    #[arg(long)]
+    output_encoding: Option<String>,
+
+    /// BCP-47 locales to translate the subtitles into, comma separated, e.g. `pt-BR,es,fr`.
+    #[arg(long, value_delimiter = ',', default_value = DEFAULT_TARGET_LOCALE)]
+    target: Vec<String>,
+
+    /// Path to the video or subtitle file (SRT, WebVTT, ASS/SSA) we want to process.
+    #[arg(required = true)]
+    input: Option<PathBuf>,
+}
+
+/// Options choosing and decoding the source subtitles, shared by translation
+/// and `glossary build` so both read the same track of every episode.
+#[derive(Args)]
+struct SourceArgs {
+    /// Language of the subtitle track to extract, as `en`, `eng` or `English`.
+    #[arg(long, default_value = DEFAULT_SOURCE_LANGUAGE)]
+    source: String,
+
+    /// Absolute index of the subtitle stream to use (see `probe`), bypassing automatic selection.
+    #[arg(long, conflicts_with = "stream_title")]
+    stream: Option<usize>,
+
+    /// Use the first subtitle stream whose title contains this text, e.g. "SDH".
+    #[arg(long)]
+    stream_title: Option<String>,
+
+    /// Track kind automatic stream selection favours: prefer-full, prefer-sdh or prefer-forced.
+    #[arg(long, default_value = "prefer-sdh")]
+    stream_policy: StreamPolicy,
+
+    /// Encoding of subtitle inputs, e.g. `windows-1252` or `utf-16`; detected when omitted.
+    #[arg(long)]
+    input_encoding: Option<String>,
+}
+
+impl SourceArgs {
+    /// The stream selection asked for; an explicit index or title wins over the policy.
+    fn stream(&self) -> StreamSelection {
+        match (self.stream, &self.stream_title) {
+            (Some(index), _) => StreamSelection::Index(index),
+            (None, Some(title)) => StreamSelection::Title(title.clone()),
+            (None, None) => StreamSelection::Auto(self.stream_policy),
+        }
+    }
+}
+
+/// Options choosing the translation service and its model, shared by
+/// translation and `glossary build`.
+#[derive(Args)]
+struct BackendArgs {
+    /// Translation service: openai, ollama (local), deepl, libretranslate, anthropic or echo
+    /// (copies the source text, for dry runs).
+    #[arg(long, default_value_t = Backend::OpenAi)]
+    backend: Backend,
+
+    /// TOML file with backend settings, e.g. an `[openai]` table with `model`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// OpenAI model translating the subtitles (overrides the config file).
+    #[arg(long)]
+    model: Option<String>,
+
+    /// OpenAI model building the glossary; defaults to `--model`.
+    #[arg(long)]
+    glossary_model: Option<String>,
+
+    /// OpenAI sampling temperature.
+    #[arg(long)]
+    temperature: Option<f32>,
+
+    /// Maximum tokens OpenAI may generate per request.
+    #[arg(long)]
+    max_output_tokens: Option<u32>,
+
+    /// Reasoning effort for OpenAI reasoning models: minimal, low, medium or high.
+    #[arg(long)]
+    reasoning_effort: Option<String>,
+
+    /// Seed for more reproducible OpenAI output.
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+impl BackendArgs {
+    /// Build the translator from the config file with the command line overrides applied.
+    fn translator(self) -> Result<Box<dyn AsyncTranslator>> {
+        let mut config = match &self.config {
+            Some(path) => BackendConfig::load(path)?,
+            None => BackendConfig::default(),
+        };
+        let openai = &mut config.openai;
+        if let Some(model) = self.model {
+            openai.model = model;
+        }
+        openai.glossary_model = self.glossary_model.or(openai.glossary_model.take());
+        openai.temperature = self.temperature.or(openai.temperature);
+        openai.max_output_tokens = self.max_output_tokens.or(openai.max_output_tokens);
+        openai.reasoning_effort = self.reasoning_effort.or(openai.reasoning_effort.take());
+        openai.seed = self.seed.or(openai.seed);
+        self.backend.translator(&config)
+    }
+}
+
+/// Subcommands that inspect files instead of translating them.
+#[derive(Subcommand)]
+enum Command {
+    /// List every subtitle stream in a video and mark the one extraction would pick.
+    Probe {
+        /// Language used to mark the stream extraction would pick.
+        #[arg(long, default_value = DEFAULT_SOURCE_LANGUAGE)]
+        source: String,
+
+        /// Policy used to mark the stream extraction would pick.
+        #[arg(long, default_value = "prefer-sdh")]
+        stream_policy: StreamPolicy,
+
+        /// Path to the video file to inspect.
+        input: PathBuf,
+    },
+    /// Manage glossary files shared by every episode of a series.
+    Glossary {
+        #[command(subcommand)]
+        command: Box<GlossaryCommand>,
+    },
+}
+
+/// Subcommands of `glossary`.
+#[derive(Subcommand)]
+enum GlossaryCommand {
+    /// Build a glossary from a sample of every input and merge it into the glossary file.
+    Build {
+        /// Glossary JSON file to create or extend.
+        #[arg(long)]
+        glossary: PathBuf,
+
+        #[command(flatten)]
+        backend: BackendArgs,
+
+        #[command(flatten)]
+        source: SourceArgs,
+
+        /// Number of subtitle lines sampled from each input.
+        #[arg(long, default_value_t = DEFAULT_GLOSSARY_SAMPLE)]
+        glossary_sample: usize,
+
+        /// Estimated tokens of source text sampled from each input.
+        #[arg(long, default_value_t = DEFAULT_GLOSSARY_TOKEN_BUDGET)]
+        glossary_token_budget: usize,
+
+        /// Locales the glossary is written for, comma separated.
+        #[arg(long, value_delimiter = ',', default_value = DEFAULT_TARGET_LOCALE)]
+        target: Vec<String>,
+
+        /// Episodes of the season, as videos or subtitle files.
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+    },
 }
 
 /// Application entry point which parses CLI args and performs actions.
@@ -42,11 +249,105 @@ fn main() -> Result<()> {
             .add_directive("warn".parse().unwrap())
     };
     tracing_subscriber::fmt().with_env_filter(filter).init();
+    match cli.command {
+        Some(Command::Probe {
+            source,
+            stream_policy,
+            input,
+        }) => return probe(&input, &source, stream_policy),
+        Some(Command::Glossary { command }) => match *command {
+            GlossaryCommand::Build {
+                glossary,
+                backend,
+                source,
+                glossary_sample,
+                glossary_token_budget,
+                target,
+                inputs,
+            } => {
+                let translator = backend.translator()?;
+                let targets: Vec<&str> = target.iter().map(String::as_str).collect();
+                let options = ProcessOptions {
+                    stream: source.stream(),
+                    source_language: source.source,
+                    input_encoding: source.input_encoding,
+                    glossary: Some(glossary),
+                    glossary_sample,
+                    glossary_token_budget,
+                    ..ProcessOptions::default()
+                };
+                build_series_glossary(&inputs, &translator, &targets, &options)?;
+                return Ok(());
+            }
+        },
+        None => {}
+    }
+    let input = cli.input.ok_or_else(|| anyhow!("missing input file"))?;
+    let stream = cli.source.stream();
     if cli.onlyextract {
-        extract_english_subtitles(&cli.input)?;
+        extract_subtitles_with(&input, &cli.source.source, &stream)?;
     } else {
-        let translator = OpenAiTranslator::new()?;
-        process_file(&cli.input, &translator, cli.batch_size)?;
+        let translator = cli.backend.translator()?;
+        let targets: Vec<&str> = cli.target.iter().map(String::as_str).collect();
+        let options = ProcessOptions {
+            source_language: cli.source.source,
+            stream,
+            batch_size: cli.batch_size,
+            token_budget: cli.token_budget,
+            concurrency: cli.concurrency,
+            input_encoding: cli.source.input_encoding,
+            output_encoding: cli.output_encoding,
+            memory: if cli.no_cache {
+                None
+            } else {
+                cli.cache.or_else(TranslationMemory::default_path)
+            },
+            glossary: cli.glossary,
+            glossary_sample: cli.glossary_sample,
+            glossary_token_budget: cli.glossary_token_budget,
+        };
+        process_file_multi(&input, &translator, &targets, &options)?;
+    }
+    Ok(())
+}
+
+/// Print the subtitle streams of `input` as a table.
+/// The stream extraction would choose for `source` under `policy` is marked with `*`.
+fn probe(input: &Path, source: &str, policy: StreamPolicy) -> Result<()> {
+    let streams = video::list_subtitle_streams(input)?;
+    if streams.is_empty() {
+        println!("no subtitle streams found in {}", input.display());
+        return Ok(());
+    }
+    let picked = video::best_stream(&streams, locale::resolve_language(source)?, policy);
+    println!(
+        "  {:<6} {:<18} {:<5} {:>7}  {:<24} title",
+        "index", "codec", "lang", "frames", "flags"
+    );
+    for (pos, stream) in streams.iter().enumerate() {
+        let mut flags = Vec::new();
+        if stream.disposition.default {
+            flags.push("default");
+        }
+        if stream.disposition.forced {
+            flags.push("forced");
+        }
+        if stream.disposition.hearing_impaired {
+            flags.push("hearing_impaired");
+        }
+        println!(
+            "{} {:<6} {:<18} {:<5} {:>7}  {:<24} {}",
+            if picked == Some(pos) { "*" } else { " " },
+            stream.index,
+            stream.codec.as_deref().unwrap_or("-"),
+            stream.language.as_deref().unwrap_or("-"),
+            stream
+                .frames
+                .map(|f| f.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            flags.join(","),
+            stream.title.as_deref().unwrap_or(""),
+        );
     }
     Ok(())
 }
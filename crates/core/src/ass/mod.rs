@@ -0,0 +1,460 @@
+//! This module is responsible for Advanced SubStation Alpha (.ass/.ssa) files.
+//! It parses the script info, styles and events while keeping every line
+//! verbatim, so only dialogue text changes when a translated file is written.
+
+use crate::subtitle::Cue;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// Event fields used when an `[Events]` section has no `Format:` line.
+const DEFAULT_EVENT_FORMAT: &[&str] = &[
+    "Layer", "Start", "End", "Style", "Name", "MarginL", "MarginR", "MarginV", "Effect", "Text",
+];
+
+/// A parsed ASS/SSA script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssDocument {
+    /// Every line of the file in order; only dialogue lines are structured.
+    pub lines: Vec<AssLine>,
+}
+
+/// A line of an ASS script.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssLine {
+    /// A `Dialogue:` event.
+    Dialogue(Dialogue),
+    /// Any other line (section headers, styles, comments), kept verbatim.
+    Raw(String),
+}
+
+/// A `Dialogue:` event split into its fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dialogue {
+    /// The line as written up to the text field, e.g. `Dialogue: 0,...,,`,
+    /// or empty when the text is not the last field.
+    pub prefix: String,
+    /// Field values in the order of the section's `Format:` line.
+    pub fields: Vec<String>,
+    /// Field names from the section's `Format:` line.
+    pub format: Vec<String>,
+}
+
+/// A style from the `[V4+ Styles]` (or `[V4 Styles]`) section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    pub name: String,
+    /// Field names and values in the order of the section's `Format:` line.
+    pub fields: Vec<(String, String)>,
+}
+
+impl Dialogue {
+    /// Return the value of the field called `name`, e.g. `Style`.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.format
+            .iter()
+            .position(|f| f.eq_ignore_ascii_case(name))
+            .and_then(|i| self.fields.get(i))
+            .map(|s| s.as_str())
+    }
+
+    /// Return the raw text field, including override tags.
+    pub fn text(&self) -> &str {
+        self.field("Text").unwrap_or("")
+    }
+
+    /// Replace the raw text field.
+    pub fn set_text(&mut self, text: String) {
+        if let Some(i) = self
+            .format
+            .iter()
+            .position(|f| f.eq_ignore_ascii_case("Text"))
+        {
+            self.fields[i] = text;
+        }
+    }
+
+    /// Start time in milliseconds.
+    pub fn start_ms(&self) -> u64 {
+        self.field("Start").and_then(parse_time).unwrap_or(0)
+    }
+
+    /// End time in milliseconds.
+    pub fn end_ms(&self) -> u64 {
+        self.field("End").and_then(parse_time).unwrap_or(0)
+    }
+}
+
+impl AssDocument {
+    /// Iterate over the dialogue events in file order.
+    pub fn dialogues(&self) -> impl Iterator<Item = &Dialogue> {
+        self.lines.iter().filter_map(|l| match l {
+            AssLine::Dialogue(d) => Some(d),
+            _ => None,
+        })
+    }
+
+    /// Return the `key: value` pairs of the `[Script Info]` section.
+    pub fn script_info(&self) -> Vec<(String, String)> {
+        let mut out = Vec::new();
+        let mut in_info = false;
+        for line in &self.lines {
+            let AssLine::Raw(line) = line else { continue };
+            if let Some(name) = section_name(line) {
+                in_info = name.eq_ignore_ascii_case("Script Info");
+                continue;
+            }
+            if in_info && !line.starts_with(';') {
+                if let Some((k, v)) = line.split_once(':') {
+                    out.push((k.trim().to_string(), v.trim().to_string()));
+                }
+            }
+        }
+        out
+    }
+
+    /// Return the styles declared in the styles section.
+    pub fn styles(&self) -> Vec<Style> {
+        let mut out = Vec::new();
+        let mut in_styles = false;
+        let mut format: Vec<String> = Vec::new();
+        for line in &self.lines {
+            let AssLine::Raw(line) = line else { continue };
+            if let Some(name) = section_name(line) {
+                in_styles = name.to_lowercase().ends_with("styles");
+                continue;
+            }
+            if !in_styles {
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("Format:") {
+                format = split_format(rest);
+            } else if let Some(rest) = line.strip_prefix("Style:") {
+                let values = rest.trim_start().split(',').map(|v| v.trim().to_string());
+                let fields: Vec<(String, String)> = format.iter().cloned().zip(values).collect();
+                let name = fields
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case("Name"))
+                    .map(|(_, v)| v.clone())
+                    .unwrap_or_default();
+                out.push(Style { name, fields });
+            }
+        }
+        out
+    }
+
+    /// Convert the dialogue events to cues with override tags masked.
+    /// Cues are numbered by dialogue position starting at 1; events without
+    /// translatable text (empty or vector drawings) are skipped.
+    pub fn to_cues(&self) -> Vec<Cue> {
+        self.dialogues()
+            .enumerate()
+            .filter_map(|(i, d)| {
+                let masked = mask(d.text());
+                if masked.text.trim().is_empty() {
+                    return None;
+                }
+                Some(Cue {
+                    index: i as u32 + 1,
+                    start_ms: d.start_ms(),
+                    end_ms: d.end_ms(),
+                    text: masked.text.split('\n').map(|s| s.to_string()).collect(),
+                    style: d.field("Style").map(|s| s.to_string()),
+                    position: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Return a copy of the script with dialogue text replaced by `cues`.
+    /// Cues are matched by the index `to_cues` assigned, and every override
+    /// tag is put back around the translated text.
+    pub fn with_cues(&self, cues: &[Cue]) -> AssDocument {
+        let by_index: HashMap<u32, &Cue> = cues.iter().map(|c| (c.index, c)).collect();
+        let mut doc = self.clone();
+        let mut position = 0u32;
+        for line in doc.lines.iter_mut() {
+            let AssLine::Dialogue(d) = line else { continue };
+            position += 1;
+            if let Some(cue) = by_index.get(&position) {
+                let masked = mask(d.text());
+                d.set_text(masked.unmask(&cue.text.join("\n")));
+            }
+        }
+        doc
+    }
+}
+
+/// Dialogue text with override tags replaced by `{N}` placeholders.
+#[derive(Debug, Clone, PartialEq)]
+struct Masked {
+    /// Tags before the first visible character, restored verbatim.
+    prefix: String,
+    /// Text sent to the translator, using `\n` for `\N` line breaks.
+    /// Soft breaks (`\n` in ASS) are masked like tags, so they stay soft.
+    text: String,
+    /// Tags and soft breaks that appear inside the text, in placeholder order.
+    tags: Vec<String>,
+    /// Tags after the last visible character, restored verbatim.
+    suffix: String,
+}
+
+impl Masked {
+    /// Rebuild ASS text from a translation of `self.text`.
+    /// Placeholders the translator dropped are appended at the end so no tag is lost.
+    fn unmask(&self, translated: &str) -> String {
+        let mut body = translated.replace('\n', "\\N");
+        let mut missing = String::new();
+        for (i, tag) in self.tags.iter().enumerate() {
+            let placeholder = format!("{{{i}}}");
+            if body.contains(&placeholder) {
+                body = body.replacen(&placeholder, tag, 1);
+            } else {
+                missing.push_str(tag);
+            }
+        }
+        format!("{}{}{}{}", self.prefix, body, missing, self.suffix)
+    }
+}
+
+/// Split ASS dialogue text into leading tags, masked text and trailing tags.
+/// Drawing commands (`{\p1}` .. `{\p0}`) are not text, so a line containing
+/// them is kept whole in the prefix.
+fn mask(text: &str) -> Masked {
+    let mut tokens: Vec<(bool, String)> = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        if rest.starts_with('{') {
+            if let Some(end) = rest.find('}') {
+                tokens.push((true, rest[..=end].to_string()));
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        let next = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| c == '{')
+            .map(|(i, _)| i)
+            .unwrap_or(rest.len());
+        tokens.push((false, rest[..next].to_string()));
+        rest = &rest[next..];
+    }
+    let is_drawing = tokens.iter().any(|(is_tag, t)| {
+        *is_tag
+            && t.match_indices("\\p")
+                .any(|(i, _)| t[i + 2..].starts_with(|c: char| ('1'..='9').contains(&c)))
+    });
+    if is_drawing {
+        return Masked {
+            prefix: text.to_string(),
+            text: String::new(),
+            tags: Vec::new(),
+            suffix: String::new(),
+        };
+    }
+    let first_text = tokens.iter().position(|(is_tag, _)| !is_tag);
+    let last_text = tokens.iter().rposition(|(is_tag, _)| !is_tag);
+    let (Some(first), Some(last)) = (first_text, last_text) else {
+        return Masked {
+            prefix: text.to_string(),
+            text: String::new(),
+            tags: Vec::new(),
+            suffix: String::new(),
+        };
+    };
+    let prefix = tokens[..first].iter().map(|(_, t)| t.as_str()).collect();
+    let suffix = tokens[last + 1..].iter().map(|(_, t)| t.as_str()).collect();
+    let mut body = String::new();
+    let mut tags = Vec::new();
+    for (is_tag, token) in &tokens[first..=last] {
+        if *is_tag {
+            body.push_str(&format!("{{{}}}", tags.len()));
+            tags.push(token.clone());
+        } else {
+            for (i, part) in token.split("\\n").enumerate() {
+                if i > 0 {
+                    body.push_str(&format!("{{{}}}", tags.len()));
+                    tags.push("\\n".to_string());
+                }
+                body.push_str(&part.replace("\\N", "\n"));
+            }
+        }
+    }
+    Masked {
+        prefix,
+        text: body,
+        tags,
+        suffix,
+    }
+}
+
+/// Parse ASS text into a script.
+/// This function should keep every line and split `Dialogue:` events using
+/// the `Format:` line of the `[Events]` section.
+pub fn parse(input: &str) -> Result<AssDocument> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let mut lines = Vec::new();
+    let mut section = String::new();
+    let mut event_format: Vec<String> =
+        DEFAULT_EVENT_FORMAT.iter().map(|s| s.to_string()).collect();
+    let mut saw_info = false;
+    for (n, line) in input.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if let Some(name) = section_name(line) {
+            section = name.to_lowercase();
+            saw_info |= section == "script info";
+            lines.push(AssLine::Raw(line.to_string()));
+            continue;
+        }
+        if section == "events" {
+            if let Some(rest) = line.strip_prefix("Format:") {
+                event_format = split_format(rest);
+            } else if let Some(rest) = line.strip_prefix("Dialogue:") {
+                let fields: Vec<String> = rest
+                    .trim_start()
+                    .splitn(event_format.len(), ',')
+                    .map(|s| s.to_string())
+                    .collect();
+                if fields.len() != event_format.len() {
+                    return Err(anyhow!("line {}: dialogue has too few fields", n + 1));
+                }
+                let text_last = event_format
+                    .last()
+                    .is_some_and(|f| f.eq_ignore_ascii_case("Text"));
+                let prefix = match fields.last() {
+                    Some(text) if text_last => line[..line.len() - text.len()].to_string(),
+                    _ => String::new(),
+                };
+                lines.push(AssLine::Dialogue(Dialogue {
+                    prefix,
+                    fields,
+                    format: event_format.clone(),
+                }));
+                continue;
+            }
+        }
+        lines.push(AssLine::Raw(line.to_string()));
+    }
+    if !saw_info {
+        return Err(anyhow!("missing [Script Info] section"));
+    }
+    Ok(AssDocument { lines })
+}
+
+/// Format a script back to text.
+/// The way this works is by writing raw lines verbatim and dialogue lines as
+/// their original prefix followed by the text field, so only the text can
+/// differ from the input. Dialogues without a prefix rejoin their fields.
+pub fn format(doc: &AssDocument) -> String {
+    let mut out = String::new();
+    for line in &doc.lines {
+        match line {
+            AssLine::Raw(raw) => out.push_str(raw),
+            AssLine::Dialogue(d) if !d.prefix.is_empty() => {
+                out.push_str(&d.prefix);
+                out.push_str(d.fields.last().map(String::as_str).unwrap_or(""));
+            }
+            AssLine::Dialogue(d) => {
+                out.push_str("Dialogue: ");
+                out.push_str(&d.fields.join(","));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Return the name of a `[Section]` header line.
+fn section_name(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+}
+
+/// Split the field list of a `Format:` line.
+fn split_format(rest: &str) -> Vec<String> {
+    rest.split(',').map(|s| s.trim().to_string()).collect()
+}
+
+/// Parse `H:MM:SS.cc` into milliseconds.
+fn parse_time(t: &str) -> Option<u64> {
+    let (clock, cs) = t.trim().split_once('.')?;
+    let mut parts = clock.split(':');
+    let h: u64 = parts.next()?.parse().ok()?;
+    let m: u64 = parts.next()?.parse().ok()?;
+    let s: u64 = parts.next()?.parse().ok()?;
+    let cs: u64 = cs.parse().ok()?;
+    Some(((h * 60 + m) * 60 + s) * 1000 + cs * 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "[Script Info]\nTitle: Sample\nScriptType: v4.00+\n\n[V4+ Styles]\nFormat: Name, Fontname, Fontsize, PrimaryColour\nStyle: Default,Arial,20,&H00FFFFFF\nStyle: Sign,Arial,30,&H0000FFFF\n\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\nDialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,{\\an8}Hello, {\\i1}dear{\\i0} friend\\NHow are you?\nComment: 0,0:00:03.00,0:00:04.00,Default,,0,0,0,,note to self\nDialogue: 1,0:00:05.00,0:00:06.00,Sign,,0,0,0,,{\\p1}m 0 0 l 10 10{\\p0}\n";
+
+    /// Ensure sections, styles and dialogue fields are parsed and written back verbatim.
+    #[test]
+    fn roundtrip_ass() {
+        let doc = parse(SAMPLE).unwrap();
+        assert_eq!(format(&doc), SAMPLE);
+        assert_eq!(doc.script_info()[0], ("Title".into(), "Sample".into()));
+        let styles = doc.styles();
+        assert_eq!(styles.len(), 2);
+        assert_eq!(styles[1].name, "Sign");
+        let first = doc.dialogues().next().unwrap();
+        assert_eq!(first.field("Style"), Some("Default"));
+        assert_eq!(first.start_ms(), 1000);
+        assert_eq!(first.end_ms(), 2500);
+        assert!(first.text().contains("Hello, {\\i1}dear"));
+    }
+
+    /// Ensure dialogue lines come back byte for byte whatever their spacing,
+    /// and a translation only changes the text field.
+    #[test]
+    fn keeps_dialogue_prefix() {
+        let input = "[Script Info]\nScriptType: v4.00+\n\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\nDialogue:0,0:00:01.00,0:00:02.00,Default,,0000,0000,0000,,Hello\nDialogue:  1, 0:00:03.00,0:00:04.00,Sign,Bob,0,0,0,, Bye\nComment:0,0:00:05.00,0:00:06.00,Default,,0,0,0,,note\n";
+        let doc = parse(input).unwrap();
+        assert_eq!(format(&doc), input);
+        let mut cues = doc.to_cues();
+        cues[0].text = vec!["Olá".into()];
+        let out = format(&doc.with_cues(&cues));
+        assert!(out.contains("\nDialogue:0,0:00:01.00,0:00:02.00,Default,,0000,0000,0000,,Olá\n"));
+        assert!(out.contains("\nDialogue:  1, 0:00:03.00,0:00:04.00,Sign,Bob,0,0,0,, Bye\n"));
+    }
+
+    /// Ensure override tags are masked for translation and restored afterwards.
+    #[test]
+    fn masks_and_restores_tags() {
+        let doc = parse(SAMPLE).unwrap();
+        let mut cues = doc.to_cues();
+        assert_eq!(cues.len(), 1, "drawings are not translated");
+        assert_eq!(
+            cues[0].text,
+            vec!["Hello, {0}dear{1} friend", "How are you?"]
+        );
+        cues[0].text = vec!["Olá, {0}querido{1} amigo".into(), "Como vai?".into()];
+        let out = format(&doc.with_cues(&cues));
+        assert!(out.contains(
+            "Dialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,{\\an8}Olá, {\\i1}querido{\\i0} amigo\\NComo vai?\n"
+        ));
+        assert!(out.contains("{\\p1}m 0 0 l 10 10{\\p0}"));
+        assert!(out.contains("Style: Sign,Arial,30,&H0000FFFF"));
+    }
+
+    /// Ensure tags dropped by the translator are still kept.
+    #[test]
+    fn keeps_dropped_tags() {
+        let masked = mask("{\\b1}Hi {\\i1}there");
+        assert_eq!(masked.text, "Hi {0}there");
+        assert_eq!(masked.unmask("Oi"), "{\\b1}Oi{\\i1}");
+    }
+
+    /// Ensure soft breaks are written back as soft breaks and hard ones as hard.
+    #[test]
+    fn keeps_soft_breaks() {
+        let masked = mask("Hi\\nthere\\Nfriend");
+        assert_eq!(masked.text, "Hi{0}there\nfriend");
+        assert_eq!(masked.unmask("Oi{0}aí\namigo"), "Oi\\naí\\Namigo");
+    }
+}
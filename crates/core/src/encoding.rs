@@ -0,0 +1,131 @@
+//! Character encoding detection and conversion for subtitle files.
+//! Older subtitles are often Latin-1, Windows-1252 or UTF-16, so files are
+//! decoded from bytes instead of assuming UTF-8.
+
+use anyhow::{anyhow, Result};
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use tracing::debug;
+
+/// Look up an encoding by label, e.g. `utf-8`, `latin1` or `windows-1252`.
+pub fn resolve(label: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| anyhow!("unknown encoding {label:?}"))
+}
+
+/// Guess the encoding of `bytes`.
+/// The way this works is by checking for a byte order mark first, then for
+/// valid UTF-8, then for the zero bytes UTF-16 text without a BOM has, and
+/// finally asking `chardetng` to pick a legacy code page.
+pub fn detect(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+    if let Some(encoding) = detect_utf16(bytes) {
+        return encoding;
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+/// Detect BOM-less UTF-16 from mostly ASCII text, where every other byte is zero.
+fn detect_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 4 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let pairs = bytes.len() / 2;
+    let even = bytes.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd = bytes.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+    if odd * 10 >= pairs * 9 && even == 0 {
+        Some(UTF_16LE)
+    } else if even * 10 >= pairs * 9 && odd == 0 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Decode `bytes` to text, using `encoding` when given and detecting it otherwise.
+/// A byte order mark always wins and is removed from the text.
+pub fn decode(bytes: &[u8], encoding: Option<&'static Encoding>) -> Result<String> {
+    let encoding = match Encoding::for_bom(bytes) {
+        Some((bom, _)) => bom,
+        None => encoding.unwrap_or_else(|| detect(bytes)),
+    };
+    debug!("decoding input as {}", encoding.name());
+    let (text, had_errors) = encoding.decode_with_bom_removal(bytes);
+    if had_errors {
+        return Err(anyhow!("input is not valid {}", encoding.name()));
+    }
+    Ok(text.into_owned())
+}
+
+/// Encode `text` for writing.
+/// This function should fail rather than write characters the encoding
+/// cannot represent. UTF-16 output starts with a byte order mark.
+pub fn encode(text: &str, encoding: &'static Encoding) -> Result<Vec<u8>> {
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let mut out = if encoding == UTF_16LE {
+            vec![0xff, 0xfe]
+        } else {
+            vec![0xfe, 0xff]
+        };
+        for unit in text.encode_utf16() {
+            let bytes = if encoding == UTF_16LE {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            };
+            out.extend_from_slice(&bytes);
+        }
+        return Ok(out);
+    }
+    let (bytes, _, had_errors) = encoding.encode(text);
+    if had_errors {
+        let bad = text
+            .chars()
+            .find(|c| encoding.encode(c.encode_utf8(&mut [0; 4])).2)
+            .unwrap_or('?');
+        return Err(anyhow!(
+            "character {bad:?} cannot be written as {}",
+            encoding.name()
+        ));
+    }
+    Ok(bytes.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensure BOMs, UTF-16 and legacy code pages are detected and decoded.
+    #[test]
+    fn decodes_common_encodings() {
+        let text = "1\n00:00:00,000 --> 00:00:01,000\nJá é ação, não?\n";
+        let cp1252 = encode(text, resolve("windows-1252").unwrap()).unwrap();
+        assert_eq!(decode(&cp1252, None).unwrap(), text);
+
+        let utf16 = encode(text, UTF_16LE).unwrap();
+        assert_eq!(decode(&utf16, None).unwrap(), text);
+        assert_eq!(detect(&utf16[2..]), UTF_16LE);
+
+        let mut bom = b"\xef\xbb\xbf".to_vec();
+        bom.extend_from_slice(text.as_bytes());
+        assert_eq!(decode(&bom, None).unwrap(), text);
+
+        let latin1 = resolve("latin1").unwrap();
+        assert_eq!(decode(b"caf\xe9", Some(latin1)).unwrap(), "café");
+    }
+
+    /// Ensure characters missing from the output encoding are reported.
+    #[test]
+    fn rejects_unencodable_text() {
+        let err = encode("日本", resolve("windows-1252").unwrap()).unwrap_err();
+        assert!(err.to_string().contains("'日'"));
+        assert!(resolve("klingon").is_err());
+    }
+}
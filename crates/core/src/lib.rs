@@ -1,5 +1,10 @@
 //! Core library for subtitle extraction.
 
+pub mod ass;
+pub mod encoding;
+pub mod locale;
 pub mod srt;
+pub mod subtitle;
 pub mod translate;
 pub mod video;
+pub mod vtt;
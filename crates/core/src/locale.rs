@@ -0,0 +1,250 @@
+//! Locale helpers for source and target languages.
+//! It maps BCP-47 tags to the human-readable names used in prompts and file names,
+//! and ISO 639 codes to the languages tagged on subtitle streams.
+
+use anyhow::{anyhow, Result};
+
+/// Locale used when the caller does not pick a target language.
+pub const DEFAULT_TARGET_LOCALE: &str = "pt-BR";
+
+/// Describes a supported target locale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Locale {
+    /// BCP-47 tag such as `pt-BR`.
+    pub tag: &'static str,
+    /// Human-readable language name used in prompts.
+    pub name: &'static str,
+}
+
+/// Table of target locales the translator knows how to name.
+pub const LOCALES: &[Locale] = &[
+    Locale {
+        tag: "pt-BR",
+        name: "Brazilian Portuguese",
+    },
+    Locale {
+        tag: "pt-PT",
+        name: "European Portuguese",
+    },
+    Locale {
+        tag: "es",
+        name: "Spanish",
+    },
+    Locale {
+        tag: "es-419",
+        name: "Latin American Spanish",
+    },
+    Locale {
+        tag: "fr",
+        name: "French",
+    },
+    Locale {
+        tag: "fr-CA",
+        name: "Canadian French",
+    },
+    Locale {
+        tag: "de",
+        name: "German",
+    },
+    Locale {
+        tag: "it",
+        name: "Italian",
+    },
+    Locale {
+        tag: "ja",
+        name: "Japanese",
+    },
+    Locale {
+        tag: "ko",
+        name: "Korean",
+    },
+    Locale {
+        tag: "zh-Hans",
+        name: "Simplified Chinese",
+    },
+    Locale {
+        tag: "en",
+        name: "English",
+    },
+];
+
+/// Language used for the source subtitles when the caller does not pick one.
+pub const DEFAULT_SOURCE_LANGUAGE: &str = "en";
+
+/// Describes a language by its ISO 639 codes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Language {
+    /// ISO 639-1 two-letter code, e.g. `en`.
+    pub iso1: &'static str,
+    /// ISO 639-2/B bibliographic code, e.g. `ger`.
+    pub iso2b: &'static str,
+    /// ISO 639-2/T terminology code, e.g. `deu`.
+    pub iso2t: &'static str,
+    /// English name of the language.
+    pub name: &'static str,
+}
+
+impl Language {
+    /// Check whether a stream language tag such as `eng` or `en` denotes this language.
+    pub fn matches(&self, code: &str) -> bool {
+        let code = code.trim();
+        [self.iso1, self.iso2b, self.iso2t, self.name]
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(code))
+    }
+}
+
+/// Build a `Language` table entry.
+const fn lang(
+    iso1: &'static str,
+    iso2b: &'static str,
+    iso2t: &'static str,
+    name: &'static str,
+) -> Language {
+    Language {
+        iso1,
+        iso2b,
+        iso2t,
+        name,
+    }
+}
+
+/// Table of languages we recognise on subtitle streams.
+pub const LANGUAGES: &[Language] = &[
+    lang("en", "eng", "eng", "English"),
+    lang("ja", "jpn", "jpn", "Japanese"),
+    lang("ko", "kor", "kor", "Korean"),
+    lang("zh", "chi", "zho", "Chinese"),
+    lang("es", "spa", "spa", "Spanish"),
+    lang("pt", "por", "por", "Portuguese"),
+    lang("fr", "fre", "fra", "French"),
+    lang("de", "ger", "deu", "German"),
+    lang("it", "ita", "ita", "Italian"),
+    lang("nl", "dut", "nld", "Dutch"),
+    lang("ru", "rus", "rus", "Russian"),
+    lang("pl", "pol", "pol", "Polish"),
+    lang("cs", "cze", "ces", "Czech"),
+    lang("el", "gre", "ell", "Greek"),
+    lang("ro", "rum", "ron", "Romanian"),
+    lang("sv", "swe", "swe", "Swedish"),
+    lang("tr", "tur", "tur", "Turkish"),
+    lang("ar", "ara", "ara", "Arabic"),
+    lang("he", "heb", "heb", "Hebrew"),
+    lang("fa", "per", "fas", "Persian"),
+    lang("hi", "hin", "hin", "Hindi"),
+    lang("th", "tha", "tha", "Thai"),
+    lang("vi", "vie", "vie", "Vietnamese"),
+    lang("id", "ind", "ind", "Indonesian"),
+    lang("ms", "may", "msa", "Malay"),
+];
+
+/// Resolve a language alias such as `en`, `eng` or `English`.
+/// Regional BCP-47 tags like `pt-BR` resolve through their primary subtag.
+pub fn find_language(alias: &str) -> Option<&'static Language> {
+    let alias = alias.trim();
+    LANGUAGES
+        .iter()
+        .find(|l| l.matches(alias))
+        .or_else(|| LANGUAGES.iter().find(|l| l.matches(primary_subtag(alias))))
+}
+
+/// Resolve a language alias or fail with a readable message.
+pub fn resolve_language(alias: &str) -> Result<&'static Language> {
+    find_language(alias).ok_or_else(|| anyhow!("unknown language {alias}"))
+}
+
+/// Look up `tag` in the locale table.
+/// The way this works is by matching the full tag case-insensitively (accepting
+/// `_` as separator) and falling back to the first entry sharing the primary
+/// language subtag, so `es-ES` resolves to `es` and `pt` to `pt-BR`.
+pub fn find(tag: &str) -> Option<&'static Locale> {
+    let tag = tag.trim().replace('_', "-");
+    if let Some(locale) = LOCALES.iter().find(|l| l.tag.eq_ignore_ascii_case(&tag)) {
+        return Some(locale);
+    }
+    let primary = primary_subtag(&tag);
+    LOCALES
+        .iter()
+        .find(|l| primary_subtag(l.tag).eq_ignore_ascii_case(primary))
+}
+
+/// Resolve `tag` to a supported locale or fail with the list of known tags.
+pub fn resolve(tag: &str) -> Result<&'static Locale> {
+    find(tag).ok_or_else(|| {
+        let known: Vec<&str> = LOCALES.iter().map(|l| l.tag).collect();
+        anyhow!(
+            "unsupported target locale {tag}; expected one of {}",
+            known.join(", ")
+        )
+    })
+}
+
+/// Describe several locales as one phrase, e.g. `Spanish and French`.
+/// This is used in prompts that serve more than one target language.
+pub fn describe(tags: &[&str]) -> Result<String> {
+    let names = tags
+        .iter()
+        .map(|tag| resolve(tag).map(|l| l.name))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(match names.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    })
+}
+
+/// Return the primary language subtag, e.g. `pt` for `pt-BR`.
+pub fn primary_subtag(tag: &str) -> &str {
+    tag.split(['-', '_']).next().unwrap_or(tag)
+}
+
+/// Build the file name suffix for `tag`, e.g. `pt_br` for `pt-BR`.
+pub fn file_suffix(tag: &str) -> String {
+    tag.trim().replace('-', "_").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensure tags resolve case-insensitively and fall back to the primary subtag.
+    #[test]
+    fn resolves_locale_names() {
+        assert_eq!(find("pt-BR").unwrap().name, "Brazilian Portuguese");
+        assert_eq!(find("pt_br").unwrap().tag, "pt-BR");
+        assert_eq!(find("es-ES").unwrap().name, "Spanish");
+        assert_eq!(find("pt").unwrap().tag, "pt-BR");
+        assert_eq!(find("en-US").unwrap().name, "English");
+        assert!(find("xx").is_none());
+        assert!(resolve("xx").is_err());
+    }
+
+    /// Ensure several locales are joined into a readable phrase.
+    #[test]
+    fn describes_locale_lists() {
+        assert_eq!(describe(&["es"]).unwrap(), "Spanish");
+        assert_eq!(
+            describe(&["pt-BR", "es", "fr"]).unwrap(),
+            "Brazilian Portuguese, Spanish and French"
+        );
+    }
+
+    /// Ensure ISO 639-1, 639-2/B, 639-2/T codes and names resolve to the same language.
+    #[test]
+    fn resolves_language_aliases() {
+        for alias in ["en", "eng", "English", "ENG"] {
+            assert_eq!(find_language(alias).unwrap().iso1, "en");
+        }
+        assert_eq!(find_language("ger").unwrap().iso1, "de");
+        assert_eq!(find_language("deu").unwrap().iso1, "de");
+        assert_eq!(find_language("pt-BR").unwrap().iso1, "pt");
+        assert!(find_language("xx").is_none());
+    }
+
+    /// Ensure file suffixes keep the existing `_pt_br` naming.
+    #[test]
+    fn builds_file_suffix() {
+        assert_eq!(file_suffix("pt-BR"), "pt_br");
+        assert_eq!(file_suffix("zh-Hans"), "zh_hans");
+    }
+}
@@ -3,6 +3,8 @@
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::warn;
 
 /// Represents a single SRT block (index, time range, text lines).
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -13,35 +15,162 @@ pub struct SrtBlock {
     pub text: Vec<String>,
 }
 
-/// Parse SRT text into a list of blocks.
-/// This function should read indices, times and text lines preserving order.
+/// How `parse_with` treats malformed input.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ParseMode {
+    /// Recover from common defects, renumber cues and report warnings.
+    #[default]
+    Lenient,
+    /// Fail on any defect, listing every problem found.
+    Strict,
+}
+
+/// A problem found while parsing, with the 1-based line it was found on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Blocks parsed from an SRT file plus the defects recovered from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parsed {
+    pub blocks: Vec<SrtBlock>,
+    pub warnings: Vec<ParseIssue>,
+}
+
+/// Parse SRT text into a list of blocks, recovering from common defects.
+/// Every recovered defect is logged as a warning.
 pub fn parse(input: &str) -> Result<Vec<SrtBlock>> {
+    let parsed = parse_with(input, ParseMode::Lenient)?;
+    for warning in &parsed.warnings {
+        warn!("{warning}");
+    }
+    Ok(parsed.blocks)
+}
+
+/// Parse SRT text in the given mode.
+/// This function should accept a UTF-8 BOM and any mix of `\r\n`, `\r` and `\n`
+/// line endings in both modes. Lenient mode also accepts `.` as the millisecond
+/// separator, missing or bad indices, cues not separated by a blank line and
+/// cues with unreadable times (which are dropped), then renumbers the cues from 1.
+/// Strict mode fails if any of those are found, listing all of them at once.
+pub fn parse_with(input: &str, mode: ParseMode) -> Result<Parsed> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let normalized = input.replace("\r\n", "\n").replace('\r', "\n");
+    let lines: Vec<&str> = normalized.split('\n').collect();
+    let mut issues = Vec::new();
     let mut blocks = Vec::new();
-    let mut lines = input.lines();
-    loop {
-        let index_line = match lines.next() {
-            Some(l) if !l.trim().is_empty() => l.trim(),
-            Some(_) => continue,
-            None => break,
+    let mut previous_index: Option<u32> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim();
+        if line.is_empty() {
+            i += 1;
+            continue;
+        }
+        let (index, timing) = if is_timing(line) {
+            issues.push(issue(i, "cue has no index"));
+            (None, i)
+        } else if lines.get(i + 1).is_some_and(|l| is_timing(l)) {
+            match line.parse::<u32>() {
+                Ok(index) => (Some(index), i + 1),
+                Err(_) => {
+                    issues.push(issue(i, format!("bad cue index {line:?}")));
+                    (None, i + 1)
+                }
+            }
+        } else {
+            issues.push(issue(i, format!("unexpected text {line:?} outside a cue")));
+            i += 1;
+            continue;
         };
-        let index: u32 = index_line.parse()?;
-        let time_line = lines.next().ok_or_else(|| anyhow!("missing time"))?;
-        let (start_ms, end_ms) = parse_times(time_line)?;
-        let mut text = Vec::new();
-        for line in lines.by_ref() {
-            if line.trim().is_empty() {
+
+        let mut text: Vec<String> = Vec::new();
+        let mut next = timing + 1;
+        while next < lines.len() && !lines[next].trim().is_empty() {
+            if is_timing(lines[next]) {
+                if text.last().is_some_and(|t| t.trim().parse::<u32>().is_ok()) {
+                    text.pop();
+                    next -= 1;
+                }
+                issues.push(issue(next, "missing blank line before cue"));
                 break;
             }
-            text.push(line.to_string());
+            text.push(lines[next].to_string());
+            next += 1;
+        }
+        i = next;
+
+        if let Some(index) = index {
+            if previous_index.is_some_and(|p| p.checked_add(1) != Some(index)) {
+                issues.push(issue(
+                    timing - 1,
+                    format!("cue index {index} is out of sequence"),
+                ));
+            }
+            previous_index = Some(index);
+        }
+        let time_line = lines[timing].trim();
+        let (start_ms, end_ms) = match parse_times(time_line) {
+            Ok(times) => times,
+            Err(e) => {
+                issues.push(issue(timing, format!("{e}, cue dropped")));
+                continue;
+            }
+        };
+        if uses_dot_separator(time_line) {
+            issues.push(issue(timing, "`.` used instead of `,` in times"));
         }
         blocks.push(SrtBlock {
-            index,
+            index: blocks.len() as u32 + 1,
             start_ms,
             end_ms,
             text,
         });
     }
-    Ok(blocks)
+    if mode == ParseMode::Strict && !issues.is_empty() {
+        let list: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        return Err(anyhow!("invalid SRT file:\n{}", list.join("\n")));
+    }
+    Ok(Parsed {
+        blocks,
+        warnings: issues,
+    })
+}
+
+/// Build an issue for the 0-based line `line`.
+fn issue(line: usize, message: impl Into<String>) -> ParseIssue {
+    ParseIssue {
+        line: line + 1,
+        message: message.into(),
+    }
+}
+
+/// Check whether `line` looks like a cue timing line: a timestamp, `-->` and
+/// another timestamp, optionally followed by settings.
+/// Timestamps only need the right shape here so that malformed times are
+/// still reported by `parse_times`, while dialogue such as `A --> B` is not
+/// mistaken for timing.
+fn is_timing(line: &str) -> bool {
+    let Some((start, rest)) = line.split_once("-->") else {
+        return false;
+    };
+    looks_like_time(start.trim()) && rest.split_whitespace().next().is_some_and(looks_like_time)
+}
+
+/// Check whether `t` has the shape of a timestamp such as `00:00:01,000`.
+fn looks_like_time(t: &str) -> bool {
+    t.starts_with(|c: char| c.is_ascii_digit())
+        && t.contains(':')
+        && t.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | ',' | '.'))
 }
 
 /// Format SRT blocks back to text.
@@ -60,25 +189,46 @@ pub fn format(blocks: &[SrtBlock]) -> String {
     out
 }
 
+/// Whether either time of the timing `line` separates milliseconds with `.`.
+/// Only the two timestamps are checked, so coordinates such as `X1:100.5`
+/// after the end time are not reported.
+fn uses_dot_separator(line: &str) -> bool {
+    let Some((start, rest)) = line.split_once("-->") else {
+        return false;
+    };
+    [start.trim(), rest.split_whitespace().next().unwrap_or("")]
+        .iter()
+        .any(|t| {
+            t.rfind(|c: char| !c.is_ascii_digit())
+                .is_some_and(|i| t[i..].starts_with('.'))
+        })
+}
+
 /// Parse a time range like `00:00:01,000 --> 00:00:02,000` to milliseconds.
+/// Anything after the end time, such as legacy position coordinates, is ignored.
 fn parse_times(line: &str) -> Result<(u64, u64)> {
-    let mut parts = line.split(" --> ");
-    let start = parts.next().ok_or_else(|| anyhow!("no start"))?;
-    let end = parts.next().ok_or_else(|| anyhow!("no end"))?;
-    Ok((parse_time(start)?, parse_time(end)?))
+    let (start, rest) = line
+        .split_once("-->")
+        .ok_or_else(|| anyhow!("bad time range {line:?}"))?;
+    let end = rest
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| anyhow!("bad time range {line:?}"))?;
+    Ok((parse_time(start.trim())?, parse_time(end)?))
 }
 
-/// Parse `HH:MM:SS,mmm` into milliseconds.
+/// Parse `HH:MM:SS,mmm` (or `HH:MM:SS.mmm`) into milliseconds.
 fn parse_time(t: &str) -> Result<u64> {
-    let parts: Vec<&str> = t.split([':', ',']).collect();
+    let parts: Vec<&str> = t.split([':', ',', '.']).collect();
     if parts.len() != 4 {
-        return Err(anyhow!("bad time"));
+        return Err(anyhow!("bad time {t:?}"));
     }
-    let h: u64 = parts[0].parse()?;
-    let m: u64 = parts[1].parse()?;
-    let s: u64 = parts[2].parse()?;
-    let ms: u64 = parts[3].parse()?;
-    Ok(((h * 60 + m) * 60 + s) * 1000 + ms)
+    let fields = parts
+        .iter()
+        .map(|p| p.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| anyhow!("bad time {t:?}"))?;
+    Ok(((fields[0] * 60 + fields[1]) * 60 + fields[2]) * 1000 + fields[3])
 }
 
 /// Format milliseconds back to `HH:MM:SS,mmm`.
@@ -103,4 +253,53 @@ mod tests {
         let out = format(&blocks);
         assert_eq!(input, out);
     }
+
+    const MESSY: &str = "\u{feff}1\r\n00:00:01.000 --> 00:00:02,000\r\nHello\n\nx\n00:00:03,000 --> 00:00:04,000\rWorld\n7\n00:00:05,000 --> 00:00:06,000\nAgain\n\n8\n00:00:07,000 --> 00:0X:08,000\nBroken\n";
+
+    /// Ensure lenient parsing recovers from common defects and renumbers cues.
+    #[test]
+    fn lenient_recovers() {
+        let parsed = parse_with(MESSY, ParseMode::Lenient).unwrap();
+        let texts: Vec<&str> = parsed.blocks.iter().map(|b| b.text[0].as_str()).collect();
+        assert_eq!(texts, vec!["Hello", "World", "Again"]);
+        let indices: Vec<u32> = parsed.blocks.iter().map(|b| b.index).collect();
+        assert_eq!(indices, vec![1, 2, 3]);
+        assert_eq!(parsed.blocks[0].start_ms, 1000);
+        let lines: Vec<usize> = parsed.warnings.iter().map(|w| w.line).collect();
+        assert_eq!(lines, vec![2, 5, 8, 8, 13]);
+        assert!(parsed.warnings[4].message.contains("cue dropped"));
+    }
+
+    /// Ensure strict parsing reports every problem in one error.
+    #[test]
+    fn strict_lists_all_errors() {
+        let err = parse_with(MESSY, ParseMode::Strict)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("line 2: `.` used"));
+        assert!(err.contains("line 5: bad cue index \"x\""));
+        assert!(err.contains("line 13: bad time \"00:0X:08,000\""));
+        assert!(parse_with("1\n00:00:00,000 --> 00:00:01,000\nHi\n", ParseMode::Strict).is_ok());
+    }
+
+    /// Ensure arrows in dialogue stay text and huge indices do not overflow.
+    #[test]
+    fn keeps_arrows_and_huge_indices() {
+        let input = "4294967295\n00:00:00,000 --> 00:00:01,000\nA --> B\n\n1\n00:00:02,000 --> 00:00:03,000\nC\n";
+        let parsed = parse_with(input, ParseMode::Lenient).unwrap();
+        assert_eq!(parsed.blocks.len(), 2);
+        assert_eq!(parsed.blocks[0].text, vec!["A --> B"]);
+        assert_eq!(parsed.warnings.len(), 1);
+        assert!(parsed.warnings[0].message.contains("out of sequence"));
+    }
+
+    /// Ensure only the timestamps are checked for `.` separators.
+    #[test]
+    fn checks_separator_of_times_only() {
+        let input = "1\n00:00:00,000 --> 00:00:01,000 X1:100.5 X2:200.5\nHi\n";
+        let parsed = parse_with(input, ParseMode::Lenient).unwrap();
+        assert!(parsed.warnings.is_empty());
+        assert!(uses_dot_separator("00:00:00,000 --> 00:00:01.000"));
+        assert!(!uses_dot_separator("00:00:00,000 --> 00:00:01,000"));
+    }
 }
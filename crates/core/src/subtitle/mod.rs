@@ -0,0 +1,256 @@
+//! Format-neutral subtitle model.
+//! Every supported format is read into a `SubtitleDocument` of `Cue`s, so the
+//! translation pipeline works the same way for SRT, WebVTT and ASS files.
+
+use crate::{ass, encoding, srt, vtt};
+use anyhow::{anyhow, Result};
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tracing::trace;
+
+/// A single timed subtitle cue.
+/// The serialized form matches `SrtBlock`, so partial files written by older
+/// versions still load.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cue {
+    /// Index used to pair source and translated lines; unique within a document.
+    pub index: u32,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: Vec<String>,
+    /// Named style, e.g. the ASS `Style` field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+    /// Positioning hints, e.g. WebVTT cue settings like `align:start`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<String>,
+}
+
+/// File formats a `SubtitleDocument` can be read from and written to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+    Ass,
+    /// SubStation Alpha v4, parsed like ASS but written back as `.ssa`.
+    Ssa,
+}
+
+impl SubtitleFormat {
+    /// Pick the format from a file extension such as `srt` or `ssa`.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "srt" => Some(SubtitleFormat::Srt),
+            "vtt" => Some(SubtitleFormat::Vtt),
+            "ass" => Some(SubtitleFormat::Ass),
+            "ssa" => Some(SubtitleFormat::Ssa),
+            _ => None,
+        }
+    }
+
+    /// Pick the format from the extension of `path`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|e| Self::from_extension(&e.to_string_lossy()))
+    }
+
+    /// File extension used when writing this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+            SubtitleFormat::Ass => "ass",
+            SubtitleFormat::Ssa => "ssa",
+        }
+    }
+}
+
+/// A subtitle file as a list of cues plus what is needed to write it back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleDocument {
+    /// Format the document was read from and will be written in.
+    pub format: SubtitleFormat,
+    /// Translatable cues in file order.
+    pub cues: Vec<Cue>,
+    /// Document-level metadata, e.g. WebVTT headers or ASS script info.
+    pub metadata: BTreeMap<String, String>,
+    /// Format-specific parts (styles, comments, tags) kept for writing.
+    source: Source,
+}
+
+/// The parsed file in its own format.
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    Srt,
+    Vtt(vtt::VttDocument),
+    Ass(ass::AssDocument),
+}
+
+impl SubtitleDocument {
+    /// Parse `content` as `format`.
+    pub fn parse(content: &str, format: SubtitleFormat) -> Result<Self> {
+        match format {
+            SubtitleFormat::Srt => Ok(Self::from_srt(srt::parse(content)?)),
+            SubtitleFormat::Vtt => {
+                let doc = vtt::parse(content)?;
+                let metadata = doc
+                    .header
+                    .iter()
+                    .skip(1)
+                    .filter_map(|l| l.split_once(':'))
+                    .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                    .collect();
+                Ok(Self {
+                    format,
+                    cues: doc.to_cues(),
+                    metadata,
+                    source: Source::Vtt(doc),
+                })
+            }
+            SubtitleFormat::Ass | SubtitleFormat::Ssa => {
+                let doc = ass::parse(content)?;
+                Ok(Self {
+                    format,
+                    cues: doc.to_cues(),
+                    metadata: doc.script_info().into_iter().collect(),
+                    source: Source::Ass(doc),
+                })
+            }
+        }
+    }
+
+    /// Read a subtitle file, picking the parser from its extension.
+    /// The character encoding is detected.
+    pub fn read(path: &Path) -> Result<Self> {
+        Self::read_with(path, None)
+    }
+
+    /// Read a subtitle file decoded as `encoding`, or a detected encoding if `None`.
+    pub fn read_with(path: &Path, encoding: Option<&'static Encoding>) -> Result<Self> {
+        trace!("SubtitleDocument::read path={}", path.display());
+        let format = SubtitleFormat::from_path(path)
+            .ok_or_else(|| anyhow!("unsupported subtitle file {}", path.display()))?;
+        let content = encoding::decode(&fs::read(path)?, encoding)?;
+        Self::parse(&content, format)
+    }
+
+    /// Build an SRT document from parsed blocks.
+    pub fn from_srt(blocks: Vec<srt::SrtBlock>) -> Self {
+        Self {
+            format: SubtitleFormat::Srt,
+            cues: blocks.into_iter().map(Cue::from).collect(),
+            metadata: BTreeMap::new(),
+            source: Source::Srt,
+        }
+    }
+
+    /// Return a copy of the document with its cues replaced by `cues`.
+    pub fn with_cues(&self, cues: Vec<Cue>) -> Self {
+        Self {
+            cues,
+            ..self.clone()
+        }
+    }
+
+    /// Write the document back in its format.
+    /// The way this works is by merging the cues into the format-specific
+    /// document, so everything but the cue text is kept as it was read.
+    pub fn format(&self) -> String {
+        match &self.source {
+            Source::Srt => {
+                let blocks: Vec<srt::SrtBlock> =
+                    self.cues.iter().map(srt::SrtBlock::from).collect();
+                srt::format(&blocks)
+            }
+            Source::Vtt(doc) => vtt::format(&doc.with_cues(&self.cues)),
+            Source::Ass(doc) => ass::format(&doc.with_cues(&self.cues)),
+        }
+    }
+}
+
+impl From<srt::SrtBlock> for Cue {
+    fn from(block: srt::SrtBlock) -> Self {
+        Self {
+            index: block.index,
+            start_ms: block.start_ms,
+            end_ms: block.end_ms,
+            text: block.text,
+            style: None,
+            position: None,
+        }
+    }
+}
+
+impl From<&Cue> for srt::SrtBlock {
+    fn from(cue: &Cue) -> Self {
+        Self {
+            index: cue.index,
+            start_ms: cue.start_ms,
+            end_ms: cue.end_ms,
+            text: cue.text.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensure each format is read into cues and written back in that format.
+    #[test]
+    fn reads_and_writes_each_format() {
+        let srt_doc = SubtitleDocument::parse(
+            "1\n00:00:00,000 --> 00:00:01,000\nHi\n\n",
+            SubtitleFormat::Srt,
+        )
+        .unwrap();
+        assert_eq!(srt_doc.cues[0].text, vec!["Hi"]);
+        assert_eq!(srt_doc.format(), "1\n00:00:00,000 --> 00:00:01,000\nHi\n\n");
+
+        let vtt_doc = SubtitleDocument::parse(
+            "WEBVTT\nKind: captions\n\n00:01.000 --> 00:02.000 line:0\nHi\n",
+            SubtitleFormat::Vtt,
+        )
+        .unwrap();
+        assert_eq!(vtt_doc.metadata["Kind"], "captions");
+        assert_eq!(vtt_doc.cues[0].position.as_deref(), Some("line:0"));
+        let mut cues = vtt_doc.cues.clone();
+        cues[0].text = vec!["Oi".into()];
+        assert!(vtt_doc
+            .with_cues(cues)
+            .format()
+            .contains("00:00:01.000 --> 00:00:02.000 line:0\nOi\n"));
+
+        let ass_doc = SubtitleDocument::parse(
+            "[Script Info]\nTitle: T\n\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\nDialogue: 0,0:00:01.00,0:00:02.00,Sign,,0,0,0,,Hi\n",
+            SubtitleFormat::Ass,
+        )
+        .unwrap();
+        assert_eq!(ass_doc.metadata["Title"], "T");
+        assert_eq!(ass_doc.cues[0].style.as_deref(), Some("Sign"));
+        assert_eq!(ass_doc.cues[0].start_ms, 1000);
+
+        let ssa = SubtitleFormat::from_extension("SSA").unwrap();
+        assert_eq!(ssa.extension(), "ssa");
+        let ssa_doc = SubtitleDocument::parse(
+            "[Script Info]\nScriptType: v4.00\n\n[V4 Styles]\nFormat: Name\nStyle: Default\n\n[Events]\nFormat: Marked, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\nDialogue: Marked=0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Hi\n",
+            ssa,
+        )
+        .unwrap();
+        assert_eq!(ssa_doc.format, SubtitleFormat::Ssa);
+        assert_eq!(ssa_doc.cues[0].text, vec!["Hi"]);
+        assert!(ssa_doc.format().contains("[V4 Styles]"));
+    }
+
+    /// Ensure partial files holding serialized `SrtBlock`s still load as cues.
+    #[test]
+    fn deserializes_srt_block_json() {
+        let json = r#"[{"index":1,"start_ms":0,"end_ms":1000,"text":["a"]}]"#;
+        let cues: Vec<Cue> = serde_json::from_str(json).unwrap();
+        assert_eq!(cues[0].text, vec!["a"]);
+        assert_eq!(cues[0].style, None);
+    }
+}
@@ -0,0 +1,207 @@
+//! Anthropic-backed translator implementation.
+//! This uses the Messages API with the same prompts as the OpenAI backend.
+
+use super::{
+    block_on,
+    http::{self, RetryPolicy},
+    prompt, AsyncTranslator, Glossary, IndexedLine, Translator,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::{debug, trace};
+
+/// Model used when `ANTHROPIC_MODEL` is not set.
+const DEFAULT_MODEL: &str = "claude-haiku-4-5";
+
+/// Version header value required by the Messages API.
+const API_VERSION: &str = "2023-06-01";
+
+/// Upper bound on the tokens generated per request.
+const MAX_TOKENS: u32 = 8192;
+
+/// Translator that delegates to the Anthropic Messages API.
+pub struct AnthropicTranslator {
+    client: Client,
+    retry: RetryPolicy,
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+impl AnthropicTranslator {
+    /// Create a new translator reading the API key from `ANTHROPIC_API_KEY`.
+    /// `ANTHROPIC_BASE_URL`, `ANTHROPIC_MODEL` and `ANTHROPIC_TIMEOUT_SECS`
+    /// override the endpoint, model and request timeout.
+    pub fn new() -> Result<Self> {
+        trace!("AnthropicTranslator::new");
+        let key = std::env::var("ANTHROPIC_API_KEY")?;
+        let base = std::env::var("ANTHROPIC_BASE_URL")
+            .unwrap_or_else(|_| "https://api.anthropic.com".to_string());
+        let model = std::env::var("ANTHROPIC_MODEL").unwrap_or_else(|_| DEFAULT_MODEL.to_string());
+        let timeout = std::env::var("ANTHROPIC_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(180);
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .timeout(Duration::from_secs(timeout))
+            .build()?;
+        debug!("using base_url={base} model={model}");
+        Ok(Self {
+            client,
+            retry: RetryPolicy::default(),
+            api_key: key,
+            base_url: base,
+            model,
+        })
+    }
+
+    /// Use `retry` instead of the default retry policy.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Send `system` and a single user message and return the text of the reply.
+    async fn post_message(&self, system: &str, user: &str) -> Result<String> {
+        trace!("post_message");
+        let body = json!({
+            "model": self.model,
+            "max_tokens": MAX_TOKENS,
+            "system": system,
+            "messages": [{ "role": "user", "content": user }],
+        });
+        debug!(request = %body);
+        let url = format!("{}/v1/messages", self.base_url);
+        let value: Value = http::send_json("Anthropic", &self.retry, || {
+            self.client
+                .post(&url)
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", API_VERSION)
+                .json(&body)
+        })
+        .await?;
+        value["content"]
+            .as_array()
+            .and_then(|blocks| blocks.iter().find_map(|b| b["text"].as_str()))
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("missing content"))
+    }
+}
+
+#[async_trait]
+impl AsyncTranslator for AnthropicTranslator {
+    /// Translate a batch of subtitle lines, using summary and previous context.
+    async fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        trace!("translate_batch lines={} prev={}", lines.len(), prev.len());
+        let (system_prompt, user_prompt) =
+            prompt::translation(summary, prev, lines, source_language, target_locale)?;
+        let content = self.post_message(&system_prompt, &user_prompt).await?;
+        prompt::parse_translated_lines(&content)
+    }
+
+    /// Ask the model for a summary and glossary based on sample lines.
+    async fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        trace!("build_glossary sample_lines={}", sample.len());
+        let system_prompt = prompt::glossary(target_locales)?;
+        let content = self
+            .post_message(&system_prompt, &sample.join("\n"))
+            .await?;
+        prompt::parse_glossary(&content, target_locales)
+    }
+
+    /// Name used to key translation memory entries.
+    fn model(&self) -> String {
+        format!("anthropic/{}", self.model)
+    }
+}
+
+impl Translator for AnthropicTranslator {
+    /// Blocking version of `AsyncTranslator::translate_batch`.
+    fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        block_on(AsyncTranslator::translate_batch(
+            self,
+            summary,
+            prev,
+            lines,
+            source_language,
+            target_locale,
+        ))?
+    }
+
+    /// Blocking version of `AsyncTranslator::build_glossary`.
+    fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        block_on(AsyncTranslator::build_glossary(
+            self,
+            sample,
+            target_locales,
+        ))?
+    }
+
+    fn model(&self) -> String {
+        AsyncTranslator::model(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::MockServer;
+
+    /// Verify that we can translate a batch using a mocked Messages API.
+    #[tokio::test]
+    async fn translates_with_mock_server() {
+        let server = MockServer::start_async().await;
+        std::env::set_var("ANTHROPIC_API_KEY", "test");
+        std::env::set_var("ANTHROPIC_BASE_URL", server.base_url());
+        let m = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/v1/messages")
+                    .header("x-api-key", "test")
+                    .header("anthropic-version", API_VERSION)
+                    .body_contains("Korean subtitles to French");
+                let text = serde_json::to_string(&json!({
+                    "translatedLines": [{"index": "1", "translation": "salut"}]
+                }))
+                .unwrap();
+                then.status(200).json_body(json!({
+                    "content": [{"type": "text", "text": text}]
+                }));
+            })
+            .await;
+        let tr = AnthropicTranslator::new().unwrap();
+        let out = AsyncTranslator::translate_batch(
+            &tr,
+            "sum",
+            &[],
+            &[IndexedLine {
+                index: 1,
+                text: "hi".into(),
+            }],
+            "kor",
+            "fr",
+        )
+        .await
+        .unwrap();
+        assert_eq!(out[0].text, "salut");
+        m.assert_async().await;
+    }
+}
@@ -0,0 +1,178 @@
+//! Registry of translation backends selectable at runtime.
+//! Each backend reads its own settings (keys, endpoints, models) from the
+//! environment when it is created; request parameters can also come from a
+//! TOML config file.
+
+use super::{
+    anthropic::AnthropicTranslator,
+    deepl::DeepLTranslator,
+    echo::EchoTranslator,
+    libretranslate::LibreTranslateTranslator,
+    ollama::OllamaTranslator,
+    openai::{OpenAiConfig, OpenAiTranslator},
+    AsyncTranslator, RetryPolicy,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+/// Backend settings loaded from a config file, one table per backend.
+/// ```toml
+/// [openai]
+/// model = "gpt-5-mini"
+/// glossary_model = "gpt-5"
+///
+/// [retry]
+/// max_attempts = 8
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackendConfig {
+    pub openai: OpenAiConfig,
+    /// Retry policy shared by every HTTP backend.
+    pub retry: RetryPolicy,
+}
+
+impl BackendConfig {
+    /// Read the config from a TOML file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("cannot read config {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("invalid config {}", path.display()))
+    }
+}
+
+/// A translation service the pipeline can use.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Backend {
+    /// OpenAI chat completions, configured with `OPENAI_*` variables.
+    #[default]
+    OpenAi,
+    /// Local Ollama or llama.cpp server, configured with `OLLAMA_*` variables.
+    Ollama,
+    /// DeepL machine translation, configured with `DEEPL_*` variables.
+    DeepL,
+    /// LibreTranslate server, configured with `LIBRETRANSLATE_*` variables.
+    LibreTranslate,
+    /// Anthropic Messages API, configured with `ANTHROPIC_*` variables.
+    Anthropic,
+    /// Returns the source text unchanged; needs no configuration.
+    Echo,
+}
+
+impl Backend {
+    /// Every backend, in the order they are listed to users.
+    pub const ALL: &'static [Backend] = &[
+        Backend::OpenAi,
+        Backend::Ollama,
+        Backend::DeepL,
+        Backend::LibreTranslate,
+        Backend::Anthropic,
+        Backend::Echo,
+    ];
+
+    /// Name used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::OpenAi => "openai",
+            Backend::Ollama => "ollama",
+            Backend::DeepL => "deepl",
+            Backend::LibreTranslate => "libretranslate",
+            Backend::Anthropic => "anthropic",
+            Backend::Echo => "echo",
+        }
+    }
+
+    /// Create the translator for this backend from its environment settings
+    /// and its table in `config`.
+    pub fn translator(self, config: &BackendConfig) -> Result<Box<dyn AsyncTranslator>> {
+        info!("using {} backend", self.name());
+        let retry = config.retry.clone();
+        Ok(match self {
+            Backend::OpenAi => Box::new(
+                OpenAiTranslator::with_config(config.openai.clone())?.with_retry_policy(retry),
+            ),
+            Backend::Ollama => Box::new(OllamaTranslator::new()?.with_retry_policy(retry)),
+            Backend::DeepL => Box::new(DeepLTranslator::new()?.with_retry_policy(retry)),
+            Backend::LibreTranslate => {
+                Box::new(LibreTranslateTranslator::new()?.with_retry_policy(retry))
+            }
+            Backend::Anthropic => Box::new(AnthropicTranslator::new()?.with_retry_policy(retry)),
+            Backend::Echo => Box::new(EchoTranslator),
+        })
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Backend::ALL
+            .iter()
+            .copied()
+            .find(|b| b.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Backend::ALL.iter().map(|b| b.name()).collect();
+                anyhow!("unknown backend {s}; expected one of {}", names.join(", "))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translate::IndexedLine;
+    use std::time::Duration;
+
+    /// Ensure backend names parse and the echo backend works without configuration.
+    #[tokio::test]
+    async fn parses_and_builds_backends() {
+        for backend in Backend::ALL {
+            assert_eq!(backend.name().parse::<Backend>().unwrap(), *backend);
+        }
+        assert!("babelfish".parse::<Backend>().is_err());
+        let tr = Backend::Echo.translator(&BackendConfig::default()).unwrap();
+        let line = IndexedLine {
+            index: 4,
+            text: "hi".into(),
+        };
+        let out = tr
+            .translate_batch("", &[], std::slice::from_ref(&line), "en", "fr")
+            .await
+            .unwrap();
+        assert_eq!(out, vec![line]);
+    }
+
+    /// Ensure config files fill in the backend tables and reject unknown keys.
+    #[test]
+    fn loads_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("subtra.toml");
+        fs::write(
+            &path,
+            "[openai]\nmodel = \"gpt-5-mini\"\ntemperature = 0.2\n\n[openai.extra_body]\nservice_tier = \"flex\"\n\n[retry]\nmax_attempts = 3\ninitial_backoff = 500\n",
+        )
+        .unwrap();
+        let config = BackendConfig::load(&path).unwrap();
+        assert_eq!(config.openai.model, "gpt-5-mini");
+        assert_eq!(config.openai.temperature, Some(0.2));
+        assert_eq!(config.openai.glossary_model, None);
+        assert_eq!(config.openai.extra_body["service_tier"], "flex");
+        assert_eq!(config.retry.max_attempts, 3);
+        assert_eq!(config.retry.initial_backoff, Duration::from_millis(500));
+        assert_eq!(config.retry.max_backoff, RetryPolicy::default().max_backoff);
+
+        fs::write(&path, "[openai]\nmodle = \"x\"\n").unwrap();
+        assert!(BackendConfig::load(&path).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! Splitting cues into the batches sent to the translator.
+//! Batches either hold a fixed number of cues or are packed by estimated
+//! token count, ending where the scene changes when possible.
+
+use crate::subtitle::Cue;
+use std::ops::Range;
+
+/// Silence between two cues, in milliseconds, taken as a scene change.
+const SCENE_GAP_MS: u64 = 4000;
+
+/// Tokens added per cue for its index and the JSON around it.
+const CUE_OVERHEAD_TOKENS: usize = 8;
+
+/// Rough token count of `text`: about four ASCII characters per token, and
+/// one token per other character, which covers CJK scripts.
+pub(crate) fn estimate_tokens(text: &str) -> usize {
+    let ascii = text.bytes().filter(u8::is_ascii).count();
+    let other = text.chars().filter(|c| !c.is_ascii()).count();
+    ascii.div_ceil(4) + other
+}
+
+/// Estimated tokens `cue` takes up in a request.
+fn cue_tokens(cue: &Cue) -> usize {
+    CUE_OVERHEAD_TOKENS + cue.text.iter().map(|l| estimate_tokens(l)).sum::<usize>()
+}
+
+/// Split `cues` into consecutive batches, returned as position ranges.
+/// Without `token_budget` every batch holds `batch_size` cues. With it, cues
+/// are added while the estimate stays within budget, still capped at
+/// `batch_size`; a cue over budget on its own gets a batch to itself. When a
+/// batch fills up it is cut at the widest scene gap in its second half, so
+/// the model sees whole scenes together.
+pub(crate) fn plan(
+    cues: &[Cue],
+    batch_size: usize,
+    token_budget: Option<usize>,
+) -> Vec<Range<usize>> {
+    let batch_size = batch_size.max(1);
+    let Some(budget) = token_budget else {
+        return (0..cues.len())
+            .step_by(batch_size)
+            .map(|start| start..(start + batch_size).min(cues.len()))
+            .collect();
+    };
+    let mut batches = Vec::new();
+    let mut start = 0;
+    while start < cues.len() {
+        let mut end = start;
+        let mut tokens = 0;
+        while end < cues.len() && end - start < batch_size {
+            let cost = cue_tokens(&cues[end]);
+            if end > start && tokens + cost > budget {
+                break;
+            }
+            tokens += cost;
+            end += 1;
+        }
+        if end < cues.len() {
+            end = scene_break(cues, start, end).unwrap_or(end);
+        }
+        batches.push(start..end);
+        start = end;
+    }
+    batches
+}
+
+/// End for the batch `start..end` at the widest scene gap in its second half,
+/// or `None` when the cues there follow each other closely.
+fn scene_break(cues: &[Cue], start: usize, end: usize) -> Option<usize> {
+    let from = (start + (end - start).div_ceil(2)).max(start + 1);
+    (from..=end)
+        .map(|pos| (cues[pos].start_ms.saturating_sub(cues[pos - 1].end_ms), pos))
+        .filter(|(gap, _)| *gap >= SCENE_GAP_MS)
+        .max()
+        .map(|(_, pos)| pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(start_ms: u64, text: &str) -> Cue {
+        Cue {
+            index: 0,
+            start_ms,
+            end_ms: start_ms + 1000,
+            text: vec![text.to_string()],
+            style: None,
+            position: None,
+        }
+    }
+
+    /// Ensure token estimates grow with text and count CJK characters individually.
+    #[test]
+    fn estimates_tokens() {
+        assert_eq!(estimate_tokens("Yeah."), 2);
+        assert_eq!(estimate_tokens("こんにちは"), 5);
+        assert!(estimate_tokens(&"word ".repeat(100)) > 100);
+    }
+
+    /// Ensure batches follow the token budget, the line cap and scene gaps.
+    #[test]
+    fn packs_by_tokens_and_scenes() {
+        let short: Vec<Cue> = (0..10).map(|i| cue(i * 1000, "Yeah.")).collect();
+        assert_eq!(plan(&short, 4, None), vec![0..4, 4..8, 8..10]);
+        assert_eq!(plan(&short, 50, Some(1000)), vec![0..10]);
+        assert_eq!(plan(&short, 4, Some(1000)), vec![0..4, 4..8, 8..10]);
+
+        let long = "word ".repeat(200);
+        let mixed = vec![
+            cue(0, &long),
+            cue(1000, &long),
+            cue(2000, "Yeah."),
+            cue(3000, "Yeah."),
+        ];
+        assert_eq!(plan(&mixed, 50, Some(100)), vec![0..1, 1..2, 2..4]);
+
+        let mut scenes: Vec<Cue> = (0..8).map(|i| cue(i * 1000, "Yeah.")).collect();
+        for cue in &mut scenes[5..] {
+            cue.start_ms += 10_000;
+            cue.end_ms += 10_000;
+        }
+        assert_eq!(plan(&scenes, 7, Some(1000)), vec![0..5, 5..8]);
+        assert_eq!(plan(&scenes, 7, None), vec![0..7, 7..8]);
+    }
+}
@@ -0,0 +1,282 @@
+//! DeepL-backed translator implementation.
+//! Lines are sent to the `/v2/translate` multi-text endpoint; the previous
+//! lines and summary are passed as DeepL `context`, which is not translated.
+
+use super::{
+    block_on,
+    http::{self, RetryPolicy},
+    AsyncTranslator, Glossary, IndexedLine, TranslationError, Translator,
+};
+use crate::locale;
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::{debug, trace};
+
+/// Translator that delegates to the DeepL REST API.
+pub struct DeepLTranslator {
+    client: Client,
+    retry: RetryPolicy,
+    api_key: String,
+    base_url: String,
+}
+
+impl DeepLTranslator {
+    /// Create a new translator reading the API key from `DEEPL_API_KEY`.
+    /// Free-plan keys (ending in `:fx`) use the free endpoint unless
+    /// `DEEPL_BASE_URL` says otherwise. `DEEPL_TIMEOUT_SECS` overrides the
+    /// request timeout.
+    pub fn new() -> Result<Self> {
+        trace!("DeepLTranslator::new");
+        let key = std::env::var("DEEPL_API_KEY")?;
+        let base = std::env::var("DEEPL_BASE_URL").unwrap_or_else(|_| {
+            if key.ends_with(":fx") {
+                "https://api-free.deepl.com".to_string()
+            } else {
+                "https://api.deepl.com".to_string()
+            }
+        });
+        let timeout = std::env::var("DEEPL_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(60);
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .timeout(Duration::from_secs(timeout))
+            .build()?;
+        debug!("using base_url={base}");
+        Ok(Self {
+            client,
+            retry: RetryPolicy::default(),
+            api_key: key,
+            base_url: base,
+        })
+    }
+
+    /// Use `retry` instead of the default retry policy.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+/// Map a supported locale to a DeepL target language code, e.g. `pt-BR` to `PT-BR`.
+/// Variants DeepL has no code for fall back to their base language, and English
+/// becomes `EN-US` since DeepL does not accept a plain `EN` target.
+fn target_lang(target_locale: &str) -> Result<String> {
+    let locale = locale::resolve(target_locale)?;
+    Ok(match locale.tag {
+        "pt-BR" | "pt-PT" | "es-419" | "zh-Hans" => locale.tag.to_uppercase(),
+        "en" => "EN-US".to_string(),
+        tag => locale::primary_subtag(tag).to_uppercase(),
+    })
+}
+
+#[async_trait]
+impl AsyncTranslator for DeepLTranslator {
+    /// Translate a batch of subtitle lines in one request, keeping their order.
+    async fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        _source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        trace!("translate_batch lines={} prev={}", lines.len(), prev.len());
+        let mut context = summary.to_string();
+        for line in prev {
+            context.push('\n');
+            context.push_str(line);
+        }
+        let body = json!({
+            "text": lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>(),
+            "target_lang": target_lang(target_locale)?,
+            "context": context.trim(),
+            "preserve_formatting": true,
+        });
+        debug!(request = %body);
+        let url = format!("{}/v2/translate", self.base_url);
+        let value: Value = http::send_json("DeepL", &self.retry, || {
+            self.client
+                .post(&url)
+                .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+                .json(&body)
+        })
+        .await?;
+        let translations = value["translations"].as_array().ok_or_else(|| {
+            TranslationError::SchemaViolation("missing translations array".into())
+        })?;
+        if translations.len() != lines.len() {
+            return Err(TranslationError::SchemaViolation(format!(
+                "{} translations for {} lines",
+                translations.len(),
+                lines.len()
+            ))
+            .into());
+        }
+        lines
+            .iter()
+            .zip(translations)
+            .enumerate()
+            .map(|(i, (line, t))| {
+                let text = t["text"].as_str().ok_or_else(|| {
+                    TranslationError::SchemaViolation(format!("translation {i} has no text"))
+                })?;
+                Ok(IndexedLine {
+                    index: line.index,
+                    text: text.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// DeepL cannot summarize, so no glossary text is produced.
+    async fn build_glossary(
+        &self,
+        _sample: &[String],
+        _target_locales: &[&str],
+    ) -> Result<Glossary> {
+        Ok(Glossary::default())
+    }
+
+    /// Name used to key translation memory entries.
+    fn model(&self) -> String {
+        "deepl".to_string()
+    }
+}
+
+impl Translator for DeepLTranslator {
+    /// Blocking version of `AsyncTranslator::translate_batch`.
+    fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        block_on(AsyncTranslator::translate_batch(
+            self,
+            summary,
+            prev,
+            lines,
+            source_language,
+            target_locale,
+        ))?
+    }
+
+    /// Blocking version of `AsyncTranslator::build_glossary`.
+    fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        block_on(AsyncTranslator::build_glossary(
+            self,
+            sample,
+            target_locales,
+        ))?
+    }
+
+    fn model(&self) -> String {
+        AsyncTranslator::model(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::MockServer;
+
+    /// Verify batches map to the multi-text endpoint and back to their indices,
+    /// and that an answer with a missing translation is rejected.
+    #[tokio::test]
+    async fn translates_with_mock_server() {
+        let server = MockServer::start_async().await;
+        std::env::set_var("DEEPL_API_KEY", "test");
+        std::env::set_var("DEEPL_BASE_URL", server.base_url());
+        let m = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/v2/translate")
+                    .header("Authorization", "DeepL-Auth-Key test")
+                    .json_body(json!({
+                        "text": ["hi", "bye"],
+                        "target_lang": "PT-BR",
+                        "context": "sum\nbefore",
+                        "preserve_formatting": true,
+                    }));
+                then.status(200).json_body(json!({
+                    "translations": [
+                        {"detected_source_language": "EN", "text": "oi"},
+                        {"detected_source_language": "EN", "text": "tchau"},
+                    ]
+                }));
+            })
+            .await;
+        let tr = DeepLTranslator::new().unwrap();
+        let lines = [
+            IndexedLine {
+                index: 7,
+                text: "hi".into(),
+            },
+            IndexedLine {
+                index: 8,
+                text: "bye".into(),
+            },
+        ];
+        let out = AsyncTranslator::translate_batch(
+            &tr,
+            "sum",
+            &["before".to_string()],
+            &lines,
+            "en",
+            "pt-BR",
+        )
+        .await
+        .unwrap();
+        assert_eq!(out[1].index, 8);
+        assert_eq!(out[1].text, "tchau");
+        m.assert_async().await;
+
+        let short = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/v2/translate")
+                    .body_contains("\"short\"");
+                then.status(200).json_body(json!({
+                    "translations": [{"detected_source_language": "EN", "text": "curto"}]
+                }));
+            })
+            .await;
+        let lines = [
+            IndexedLine {
+                index: 1,
+                text: "short".into(),
+            },
+            IndexedLine {
+                index: 2,
+                text: "dropped".into(),
+            },
+        ];
+        let err = AsyncTranslator::translate_batch(&tr, "", &[], &lines, "en", "pt-BR")
+            .await
+            .unwrap_err()
+            .downcast::<TranslationError>()
+            .unwrap();
+        assert_eq!(
+            err,
+            TranslationError::SchemaViolation("1 translations for 2 lines".into())
+        );
+        short.assert_async().await;
+    }
+
+    /// Ensure locales map to DeepL's target language codes.
+    #[test]
+    fn maps_target_languages() {
+        assert_eq!(target_lang("pt-BR").unwrap(), "PT-BR");
+        assert_eq!(target_lang("fr-CA").unwrap(), "FR");
+        assert_eq!(target_lang("zh-Hans").unwrap(), "ZH-HANS");
+        assert_eq!(target_lang("en").unwrap(), "EN-US");
+        assert_eq!(target_lang("ja").unwrap(), "JA");
+    }
+}
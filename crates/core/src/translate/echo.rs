@@ -0,0 +1,59 @@
+//! Translator that returns the source lines unchanged.
+//! Useful for dry runs of the extraction and output pipeline without any API.
+
+use super::{AsyncTranslator, Glossary, IndexedLine, Translator};
+use anyhow::Result;
+use async_trait::async_trait;
+use tracing::trace;
+
+/// Translator that echoes every line back untranslated.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EchoTranslator;
+
+impl Translator for EchoTranslator {
+    /// Return `lines` as they are.
+    fn translate_batch(
+        &self,
+        _summary: &str,
+        _prev: &[String],
+        lines: &[IndexedLine],
+        _source_language: &str,
+        _target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        trace!("translate_batch lines={}", lines.len());
+        Ok(lines.to_vec())
+    }
+
+    /// Return an empty glossary.
+    fn build_glossary(&self, _sample: &[String], _target_locales: &[&str]) -> Result<Glossary> {
+        Ok(Glossary::default())
+    }
+
+    /// Name used to key translation memory entries.
+    fn model(&self) -> String {
+        "echo".to_string()
+    }
+}
+
+/// Echoing never blocks, so the async calls answer directly.
+#[async_trait]
+impl AsyncTranslator for EchoTranslator {
+    async fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        Translator::translate_batch(self, summary, prev, lines, source_language, target_locale)
+    }
+
+    async fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        Translator::build_glossary(self, sample, target_locales)
+    }
+
+    fn model(&self) -> String {
+        Translator::model(self)
+    }
+}
@@ -0,0 +1,318 @@
+//! Glossaries of names and terms passed to every translation prompt.
+//! A glossary is built by the model as JSON, can be kept in a file shared by
+//! every episode of a series, edited by hand, and is checked against the
+//! translated lines.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use tracing::{debug, trace};
+
+/// Summary of the video with the terms and characters translators must keep
+/// consistent.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Glossary {
+    /// Short summary of the plot and setting.
+    pub summary: String,
+    /// Names and invented words with their agreed translation.
+    pub terms: Vec<Term>,
+    /// Speaking characters, so pronouns and forms of address agree.
+    pub characters: Vec<Character>,
+}
+
+/// A source term and how it must appear in translations.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Term {
+    /// Term as it appears in the source subtitles.
+    pub source: String,
+    /// Agreed translation of the term, keyed by locale tag such as `pt-BR`.
+    pub target: BTreeMap<String, String>,
+    /// Why the translation was chosen, or how the term is used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Keep the term exactly as in the source, e.g. a name or a brand.
+    pub do_not_translate: bool,
+}
+
+/// A character of the video.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Character {
+    /// Name as it appears in the source subtitles.
+    pub name: String,
+    /// Gender used for pronouns and agreement, e.g. `female`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gender: Option<String>,
+    /// How the character speaks and is addressed, e.g. `formal` or `casual`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formality: Option<String>,
+}
+
+impl Term {
+    /// Text a translation to `locale` must contain wherever the source term
+    /// appears, or `None` when the glossary has no translation for `locale`.
+    pub fn expected(&self, locale: &str) -> Option<&str> {
+        if self.do_not_translate {
+            return Some(&self.source);
+        }
+        self.target
+            .get(locale)
+            .map(String::as_str)
+            .filter(|t| !t.trim().is_empty())
+    }
+}
+
+impl Glossary {
+    /// Load the glossary stored at `path`, starting empty when it does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        trace!("Glossary::load path={}", path.display());
+        if !path.exists() {
+            debug!("no glossary at {}, starting empty", path.display());
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("cannot read glossary {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("invalid glossary {}", path.display()))
+    }
+
+    /// Write the glossary to `path` as pretty-printed JSON, creating its directory.
+    /// The way this works is by writing a temporary file next to `path` and
+    /// renaming it over the old one, so readers never see a half-written file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        trace!("Glossary::save path={}", path.display());
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = serde_json::to_string_pretty(self)?;
+        text.push('\n');
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temp = path.with_file_name(format!(".{name}.{}.tmp", std::process::id()));
+        fs::write(&temp, text)
+            .and_then(|()| fs::rename(&temp, path))
+            .inspect_err(|_| {
+                let _ = fs::remove_file(&temp);
+            })
+            .with_context(|| format!("cannot write glossary {}", path.display()))
+    }
+
+    /// Whether the glossary has nothing to tell the translator.
+    pub fn is_empty(&self) -> bool {
+        self.summary.trim().is_empty() && self.terms.is_empty() && self.characters.is_empty()
+    }
+
+    /// Add the terms and characters of `other` that this glossary does not
+    /// have yet, compared by source text or name without case.
+    /// Existing entries and the existing summary are kept unchanged, so
+    /// entries from earlier episodes or edited by hand win over what the
+    /// model suggests later. Known terms only gain the translations for
+    /// locales they do not have yet.
+    pub fn merge(&mut self, other: &Glossary) {
+        if self.summary.trim().is_empty() {
+            self.summary = other.summary.clone();
+        }
+        for term in &other.terms {
+            if term.source.trim().is_empty() {
+                continue;
+            }
+            let key = normalize(&term.source);
+            match self.terms.iter_mut().find(|t| normalize(&t.source) == key) {
+                Some(known) => {
+                    for (locale, target) in &term.target {
+                        known
+                            .target
+                            .entry(locale.clone())
+                            .or_insert_with(|| target.clone());
+                    }
+                }
+                None => self.terms.push(term.clone()),
+            }
+        }
+        let mut known: HashSet<String> =
+            self.characters.iter().map(|c| normalize(&c.name)).collect();
+        for character in &other.characters {
+            if !character.name.trim().is_empty() && known.insert(normalize(&character.name)) {
+                self.characters.push(character.clone());
+            }
+        }
+    }
+
+    /// Format the glossary compactly for the prompt translating to `locale`,
+    /// one entry per line. Terms without a translation for `locale` are
+    /// listed without one.
+    pub fn to_prompt(&self, locale: &str) -> String {
+        let mut out = Vec::new();
+        if !self.summary.trim().is_empty() {
+            out.push(format!("Summary: {}", self.summary.trim()));
+        }
+        if !self.terms.is_empty() {
+            out.push("Terms:".to_string());
+            for term in &self.terms {
+                let mut line = match term.expected(locale) {
+                    Some(expected) => format!("- {} => {}", term.source, expected),
+                    None => format!("- {}", term.source),
+                };
+                if term.do_not_translate {
+                    line.push_str(" (keep as is)");
+                }
+                if let Some(note) = term.note.as_deref().filter(|n| !n.trim().is_empty()) {
+                    line.push_str(&format!("; {note}"));
+                }
+                out.push(line);
+            }
+        }
+        if !self.characters.is_empty() {
+            out.push("Characters:".to_string());
+            for character in &self.characters {
+                let traits: Vec<&str> = [&character.gender, &character.formality]
+                    .into_iter()
+                    .filter_map(|t| t.as_deref())
+                    .filter(|t| !t.trim().is_empty())
+                    .collect();
+                if traits.is_empty() {
+                    out.push(format!("- {}", character.name));
+                } else {
+                    out.push(format!("- {} ({})", character.name, traits.join(", ")));
+                }
+            }
+        }
+        out.join("\n")
+    }
+
+    /// Terms found in `source` whose expected text in `locale` is missing
+    /// from `translation`, with that expected text.
+    /// Matching ignores case; terms without an expected text are skipped.
+    pub fn violations(&self, source: &str, translation: &str, locale: &str) -> Vec<(&Term, &str)> {
+        let (source, translation) = (source.to_lowercase(), translation.to_lowercase());
+        self.terms
+            .iter()
+            .filter(|t| !t.source.trim().is_empty())
+            .filter_map(|t| Some((t, t.expected(locale)?)))
+            .filter(|(t, _)| source.contains(&normalize(&t.source)))
+            .filter(|(_, expected)| !translation.contains(&normalize(expected)))
+            .collect()
+    }
+}
+
+/// Compare entries without case or surrounding whitespace.
+fn normalize(text: &str) -> String {
+    text.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn term(source: &str, target: &[(&str, &str)], do_not_translate: bool) -> Term {
+        Term {
+            source: source.to_string(),
+            target: target
+                .iter()
+                .map(|(locale, text)| (locale.to_string(), text.to_string()))
+                .collect(),
+            note: None,
+            do_not_translate,
+        }
+    }
+
+    /// Ensure merging keeps existing entries, adds only new ones and new
+    /// locales, and that a saved glossary loads back in camelCase.
+    #[test]
+    fn merges_and_saves() {
+        let mut glossary = Glossary {
+            summary: "A reaper hunts spirits.".to_string(),
+            terms: vec![term("Shinigami", &[("pt-BR", "Ceifador")], false)],
+            characters: vec![],
+        };
+        glossary.merge(&Glossary {
+            summary: "Something else.".to_string(),
+            terms: vec![
+                term(
+                    "shinigami ",
+                    &[("pt-BR", "Deus da morte"), ("es", "Segador")],
+                    false,
+                ),
+                term("Zanpakuto", &[], true),
+            ],
+            characters: vec![Character {
+                name: "Kaito".to_string(),
+                gender: Some("male".to_string()),
+                formality: None,
+            }],
+        });
+        assert_eq!(glossary.summary, "A reaper hunts spirits.");
+        assert_eq!(
+            glossary.terms,
+            vec![
+                term(
+                    "Shinigami",
+                    &[("es", "Segador"), ("pt-BR", "Ceifador")],
+                    false
+                ),
+                term("Zanpakuto", &[], true)
+            ]
+        );
+        assert_eq!(glossary.characters.len(), 1);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("season").join("glossary.json");
+        assert_eq!(Glossary::load(&path).unwrap(), Glossary::default());
+        glossary.save(&path).unwrap();
+        assert_eq!(Glossary::load(&path).unwrap(), glossary);
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("\"doNotTranslate\": true"));
+        assert!(text.contains("\"pt-BR\": \"Ceifador\""));
+        fs::write(&path, "not json").unwrap();
+        assert!(Glossary::load(&path).is_err());
+    }
+
+    /// Ensure the prompt lists every entry for the given locale and violations
+    /// are found without case.
+    #[test]
+    fn formats_and_validates() {
+        let glossary = Glossary {
+            summary: "A reaper hunts spirits.".to_string(),
+            terms: vec![
+                Term {
+                    note: Some("title of the reapers".to_string()),
+                    ..term("Shinigami", &[("pt-BR", "Ceifador")], false)
+                },
+                term("Kaito", &[], true),
+            ],
+            characters: vec![Character {
+                name: "Kaito".to_string(),
+                gender: Some("male".to_string()),
+                formality: Some("casual".to_string()),
+            }],
+        };
+        assert_eq!(
+            glossary.to_prompt("pt-BR"),
+            "Summary: A reaper hunts spirits.\nTerms:\n\
+             - Shinigami => Ceifador; title of the reapers\n\
+             - Kaito => Kaito (keep as is)\nCharacters:\n- Kaito (male, casual)"
+        );
+        assert!(glossary
+            .to_prompt("es")
+            .contains("\n- Shinigami; title of the reapers\n"));
+        assert!(glossary
+            .violations("Kaito is a shinigami.", "Kaito é um ceifador.", "pt-BR")
+            .is_empty());
+        let missing = glossary.violations("Kaito is a shinigami.", "Caito é um deus.", "pt-BR");
+        assert_eq!(
+            missing
+                .iter()
+                .map(|(t, expected)| (t.source.as_str(), *expected))
+                .collect::<Vec<_>>(),
+            [("Shinigami", "Ceifador"), ("Kaito", "Kaito")]
+        );
+        let missing = glossary.violations("Kaito is a shinigami.", "Caito es un dios.", "es");
+        assert_eq!(missing.len(), 1);
+        assert!(Glossary::default().is_empty());
+        assert_eq!(Glossary::default().to_prompt("es"), "");
+    }
+}
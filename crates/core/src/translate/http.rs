@@ -0,0 +1,362 @@
+//! HTTP helpers shared by the translation backends.
+
+use anyhow::{anyhow, Result};
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, info, warn};
+
+/// When and how often failed requests are retried.
+/// Timeouts, connection failures and 429/500/502/503/504 responses are retried
+/// with exponential backoff; server hints such as `Retry-After` take precedence.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each further retry.
+    #[serde(with = "millis")]
+    pub initial_backoff: Duration,
+    /// Upper bound on the computed backoff.
+    #[serde(with = "millis")]
+    pub max_backoff: Duration,
+    /// Upper bound on delays asked for by the server, e.g. via `Retry-After`.
+    #[serde(with = "millis")]
+    pub max_server_delay: Duration,
+    /// Fraction of the backoff randomly added or removed, from 0 to 1.
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 6,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_server_delay: Duration::from_secs(300),
+            jitter: 0.25,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff before retry number `retry` (starting at 1), with jitter applied.
+    fn backoff(&self, retry: u32) -> Duration {
+        let base = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff);
+        let jitter = self.jitter.clamp(0.0, 1.0) * (random_unit() * 2.0 - 1.0);
+        base.mul_f64(1.0 + jitter)
+    }
+}
+
+/// Read durations in config files as whole milliseconds.
+mod millis {
+    use serde::{Deserialize, Deserializer};
+    use std::time::Duration;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        Ok(Duration::from_millis(u64::deserialize(d)?))
+    }
+}
+
+/// A pseudo-random number in `[0, 1)`, good enough to spread retries apart.
+fn random_unit() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Whether a response with `status` is worth retrying.
+fn retryable_status(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 500 | 502 | 503 | 504)
+}
+
+/// Whether a transport error is worth retrying: timeouts, refused or reset connections.
+fn retryable_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect() || err.is_request() || err.is_body()
+}
+
+/// Delay the server asked for, from `Retry-After` (seconds or an HTTP date) or,
+/// on 429 responses only, the longest of OpenAI's `x-ratelimit-reset-*`
+/// headers (e.g. `6m0s`, `20ms`). OpenAI sends those on every response, so
+/// they say nothing about when a server error will clear.
+fn server_delay(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if let Some(value) = headers.get("retry-after").and_then(|v| v.to_str().ok()) {
+        let value = value.trim();
+        if let Ok(secs) = value.parse::<f64>() {
+            return seconds(secs);
+        }
+        if let Ok(at) = httpdate::parse_http_date(value) {
+            return Some(at.duration_since(SystemTime::now()).unwrap_or_default());
+        }
+    }
+    if status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    headers
+        .iter()
+        .filter(|(name, _)| name.as_str().starts_with("x-ratelimit-reset"))
+        .filter_map(|(_, v)| parse_reset(v.to_str().ok()?))
+        .max()
+}
+
+/// Parse a Go-style duration such as `1h2m3.5s`, `6m0s` or `250ms`.
+fn parse_reset(value: &str) -> Option<Duration> {
+    let mut total = 0.0;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" | "" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += number * scale;
+        rest = &rest[unit_len..];
+    }
+    seconds(total)
+}
+
+/// Convert seconds sent by a server into a duration, rejecting negative,
+/// infinite or too large values instead of panicking on them.
+fn seconds(secs: f64) -> Option<Duration> {
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+/// Send the request built by `build` and return the JSON response.
+/// The way this works is by rebuilding and resending the request while
+/// `policy` allows it; errors name `provider` and, for HTTP failures, carry
+/// the status and body of the last response.
+pub(crate) async fn send_json(
+    provider: &str,
+    policy: &RetryPolicy,
+    build: impl Fn() -> RequestBuilder,
+) -> Result<Value> {
+    let attempts = policy.max_attempts.max(1);
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        info!("sending request to {provider}");
+        let start = Instant::now();
+        let result = match build().send().await {
+            Ok(resp) => {
+                let status = resp.status();
+                let headers = resp.headers().clone();
+                resp.text().await.map(|text| (status, headers, text))
+            }
+            Err(err) => Err(err),
+        };
+        let elapsed = start.elapsed().as_millis();
+        let (error, delay) = match result {
+            Ok((status, headers, text)) => {
+                info!("{provider} responded in {elapsed} ms with status {status}");
+                debug!(response = %text);
+                if status.is_success() {
+                    return Ok(serde_json::from_str(&text)?);
+                }
+                let error = anyhow!("{provider} error: {status} {text}");
+                if !retryable_status(status) {
+                    return Err(error);
+                }
+                (error, server_delay(status, &headers))
+            }
+            Err(err) => {
+                info!("{provider} request failed after {elapsed} ms");
+                debug!(?err);
+                if !retryable_error(&err) {
+                    return Err(err.into());
+                }
+                (err.into(), None)
+            }
+        };
+        if attempt >= attempts {
+            warn!("{provider} request failed {attempt} time(s), giving up");
+            return Err(error);
+        }
+        let delay = delay
+            .map(|d| d.min(policy.max_server_delay))
+            .unwrap_or_else(|| policy.backoff(attempt));
+        warn!(
+            "{error}; retrying in {} ms (attempt {}/{attempts})",
+            delay.as_millis(),
+            attempt + 1
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Client;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// Serve the canned `responses` in order on a local port, recording when each
+    /// request arrived.
+    fn serve(responses: Vec<&'static str>) -> (String, Arc<Mutex<Vec<Instant>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let arrivals = Arc::new(Mutex::new(Vec::new()));
+        let seen = arrivals.clone();
+        thread::spawn(move || {
+            for (stream, resp) in listener.incoming().zip(responses) {
+                let mut stream = stream.unwrap();
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf);
+                seen.lock().unwrap().push(Instant::now());
+                stream.write_all(resp.as_bytes()).unwrap();
+            }
+        });
+        (format!("http://{addr}"), arrivals)
+    }
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(50),
+            max_server_delay: Duration::from_secs(1),
+            jitter: 0.0,
+        }
+    }
+
+    /// Ensure rate limits and server errors are retried, honouring server delays.
+    #[tokio::test]
+    async fn retries_rate_limits_and_server_errors() {
+        let (url, arrivals) = serve(vec![
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0.3\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 429 Too Many Requests\r\nx-ratelimit-reset-requests: 20ms\r\nx-ratelimit-reset-tokens: 200ms\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n{\"ok\":true}",
+        ]);
+        let client = Client::new();
+        let value = send_json("test", &fast_policy(5), || client.post(&url))
+            .await
+            .unwrap();
+        assert_eq!(value["ok"], true);
+        let arrivals = arrivals.lock().unwrap();
+        assert_eq!(arrivals.len(), 3);
+        assert!(arrivals[1] - arrivals[0] >= Duration::from_millis(300));
+        assert!(arrivals[2] - arrivals[1] >= Duration::from_millis(200));
+    }
+
+    /// Ensure server errors back off briefly even when OpenAI's reset headers
+    /// announce a long wait, while `Retry-After` is still honoured on them.
+    #[tokio::test]
+    async fn ignores_reset_headers_on_server_errors() {
+        let (url, arrivals) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nx-ratelimit-reset-tokens: 30s\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 502 Bad Gateway\r\nRetry-After: 0.2\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n{\"ok\":true}",
+        ]);
+        let client = Client::new();
+        let policy = RetryPolicy {
+            max_server_delay: Duration::from_secs(60),
+            ..fast_policy(3)
+        };
+        let value = send_json("test", &policy, || client.post(&url))
+            .await
+            .unwrap();
+        assert_eq!(value["ok"], true);
+        let arrivals = arrivals.lock().unwrap();
+        assert!(arrivals[1] - arrivals[0] < Duration::from_secs(5));
+        assert!(arrivals[2] - arrivals[1] >= Duration::from_millis(200));
+    }
+
+    /// Ensure server delays above `max_server_delay` are capped.
+    #[tokio::test]
+    async fn caps_server_delays() {
+        let (url, arrivals) = serve(vec![
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 86400\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n{\"ok\":true}",
+        ]);
+        let client = Client::new();
+        let policy = RetryPolicy {
+            max_server_delay: Duration::from_millis(100),
+            ..fast_policy(2)
+        };
+        let value = send_json("test", &policy, || client.post(&url))
+            .await
+            .unwrap();
+        assert_eq!(value["ok"], true);
+        let arrivals = arrivals.lock().unwrap();
+        assert!(arrivals[1] - arrivals[0] < Duration::from_secs(5));
+    }
+
+    /// Ensure retries stop after `max_attempts` and client errors are not retried.
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let error = "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 4\r\n\r\noops";
+        let (url, arrivals) = serve(vec![error, error, error]);
+        let client = Client::new();
+        let err = send_json("test", &fast_policy(2), || client.post(&url))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("500"));
+        assert_eq!(arrivals.lock().unwrap().len(), 2);
+
+        let (url, arrivals) = serve(vec![
+            "HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}",
+        ]);
+        assert!(send_json("test", &fast_policy(5), || client.post(&url))
+            .await
+            .is_err());
+        assert_eq!(arrivals.lock().unwrap().len(), 1);
+    }
+
+    /// Ensure backoff grows exponentially up to the cap and reset headers parse.
+    #[test]
+    fn computes_delays() {
+        let policy = fast_policy(5);
+        assert_eq!(policy.backoff(1), Duration::from_millis(10));
+        assert_eq!(policy.backoff(3), Duration::from_millis(40));
+        assert_eq!(policy.backoff(9), Duration::from_millis(50));
+        let jittered = RetryPolicy::default().backoff(1);
+        assert!(jittered >= Duration::from_millis(750) && jittered <= Duration::from_millis(1250));
+        assert_eq!(parse_reset("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(
+            parse_reset("1h2m3.5s"),
+            Some(Duration::from_secs_f64(3723.5))
+        );
+        assert_eq!(parse_reset("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_reset("soon"), None);
+        assert_eq!(parse_reset("99999999999999999999h"), None);
+
+        let retry_after = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("retry-after", value.parse().unwrap());
+            server_delay(StatusCode::TOO_MANY_REQUESTS, &headers)
+        };
+        assert_eq!(retry_after("2.5"), Some(Duration::from_millis(2500)));
+        assert_eq!(retry_after("inf"), None);
+        assert_eq!(retry_after("NaN"), None);
+        assert_eq!(retry_after("1e30"), None);
+        assert_eq!(retry_after("-5"), None);
+    }
+}
@@ -0,0 +1,237 @@
+//! LibreTranslate-backed translator implementation.
+//! LibreTranslate can be self-hosted, and its `/translate` endpoint accepts a
+//! list of texts, so each batch is a single request.
+
+use super::{
+    block_on,
+    http::{self, RetryPolicy},
+    AsyncTranslator, Glossary, IndexedLine, TranslationError, Translator,
+};
+use crate::locale;
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::{debug, trace};
+
+/// Translator that delegates to a LibreTranslate server.
+pub struct LibreTranslateTranslator {
+    client: Client,
+    retry: RetryPolicy,
+    api_key: Option<String>,
+    base_url: String,
+}
+
+impl LibreTranslateTranslator {
+    /// Create a new translator for the server at `LIBRETRANSLATE_BASE_URL`
+    /// (default `http://localhost:5000`), sending `LIBRETRANSLATE_API_KEY` if set.
+    /// `LIBRETRANSLATE_TIMEOUT_SECS` overrides the request timeout.
+    pub fn new() -> Result<Self> {
+        trace!("LibreTranslateTranslator::new");
+        let base = std::env::var("LIBRETRANSLATE_BASE_URL")
+            .unwrap_or_else(|_| "http://localhost:5000".to_string());
+        let api_key = std::env::var("LIBRETRANSLATE_API_KEY").ok();
+        let timeout = std::env::var("LIBRETRANSLATE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(120);
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .timeout(Duration::from_secs(timeout))
+            .build()?;
+        debug!("using base_url={base}");
+        Ok(Self {
+            client,
+            retry: RetryPolicy::default(),
+            api_key,
+            base_url: base,
+        })
+    }
+
+    /// Use `retry` instead of the default retry policy.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+#[async_trait]
+impl AsyncTranslator for LibreTranslateTranslator {
+    /// Translate a batch of subtitle lines in one request, keeping their order.
+    /// LibreTranslate has no notion of context, so `summary` and `prev` are unused.
+    async fn translate_batch(
+        &self,
+        _summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        _source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        trace!("translate_batch lines={} prev={}", lines.len(), prev.len());
+        let target = locale::primary_subtag(locale::resolve(target_locale)?.tag);
+        let mut body = json!({
+            "q": lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>(),
+            "source": "auto",
+            "target": target,
+            "format": "text",
+        });
+        if let Some(key) = &self.api_key {
+            body["api_key"] = json!(key);
+        }
+        debug!(request = %body);
+        let url = format!("{}/translate", self.base_url);
+        let value: Value = http::send_json("LibreTranslate", &self.retry, || {
+            self.client.post(&url).json(&body)
+        })
+        .await?;
+        let texts = value["translatedText"].as_array().ok_or_else(|| {
+            TranslationError::SchemaViolation("missing translatedText array".into())
+        })?;
+        if texts.len() != lines.len() {
+            return Err(TranslationError::SchemaViolation(format!(
+                "{} translations for {} lines",
+                texts.len(),
+                lines.len()
+            ))
+            .into());
+        }
+        lines
+            .iter()
+            .zip(texts)
+            .enumerate()
+            .map(|(i, (line, t))| {
+                let text = t.as_str().ok_or_else(|| {
+                    TranslationError::SchemaViolation(format!("translation {i} is not text"))
+                })?;
+                Ok(IndexedLine {
+                    index: line.index,
+                    text: text.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// LibreTranslate cannot summarize, so no glossary text is produced.
+    async fn build_glossary(
+        &self,
+        _sample: &[String],
+        _target_locales: &[&str],
+    ) -> Result<Glossary> {
+        Ok(Glossary::default())
+    }
+
+    /// Name used to key translation memory entries.
+    fn model(&self) -> String {
+        "libretranslate".to_string()
+    }
+}
+
+impl Translator for LibreTranslateTranslator {
+    /// Blocking version of `AsyncTranslator::translate_batch`.
+    fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        block_on(AsyncTranslator::translate_batch(
+            self,
+            summary,
+            prev,
+            lines,
+            source_language,
+            target_locale,
+        ))?
+    }
+
+    /// Blocking version of `AsyncTranslator::build_glossary`.
+    fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        block_on(AsyncTranslator::build_glossary(
+            self,
+            sample,
+            target_locales,
+        ))?
+    }
+
+    fn model(&self) -> String {
+        AsyncTranslator::model(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::MockServer;
+
+    /// Verify batches map to the list form of `/translate` and back to their
+    /// indices, and that an item that is not text is rejected.
+    #[tokio::test]
+    async fn translates_with_mock_server() {
+        let server = MockServer::start_async().await;
+        std::env::set_var("LIBRETRANSLATE_BASE_URL", server.base_url());
+        let m = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/translate")
+                    .json_body(json!({
+                        "q": ["hi", "bye"],
+                        "source": "auto",
+                        "target": "es",
+                        "format": "text",
+                    }));
+                then.status(200)
+                    .json_body(json!({"translatedText": ["hola", "adiós"]}));
+            })
+            .await;
+        let tr = LibreTranslateTranslator::new().unwrap();
+        let lines = [
+            IndexedLine {
+                index: 3,
+                text: "hi".into(),
+            },
+            IndexedLine {
+                index: 4,
+                text: "bye".into(),
+            },
+        ];
+        let out = AsyncTranslator::translate_batch(&tr, "", &[], &lines, "en", "es-419")
+            .await
+            .unwrap();
+        assert_eq!(out[0].index, 3);
+        assert_eq!(out[1].text, "adiós");
+        m.assert_async().await;
+
+        let bad = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/translate")
+                    .body_contains("\"one\"");
+                then.status(200)
+                    .json_body(json!({"translatedText": ["uno", null]}));
+            })
+            .await;
+        let lines = [
+            IndexedLine {
+                index: 1,
+                text: "one".into(),
+            },
+            IndexedLine {
+                index: 2,
+                text: "two".into(),
+            },
+        ];
+        let err = AsyncTranslator::translate_batch(&tr, "", &[], &lines, "en", "es")
+            .await
+            .unwrap_err()
+            .downcast::<TranslationError>()
+            .unwrap();
+        assert_eq!(
+            err,
+            TranslationError::SchemaViolation("translation 1 is not text".into())
+        );
+        bad.assert_async().await;
+    }
+}
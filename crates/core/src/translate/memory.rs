@@ -0,0 +1,228 @@
+//! Translation memory shared between runs.
+//! Every translated line is appended to a JSON-lines file, keyed by its source
+//! text, a hash of the line before it, the target locale and the model, so
+//! recurring lines are not sent to the backend again.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, trace, warn};
+
+/// One line of the memory file.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    text: String,
+    context: String,
+    locale: String,
+    model: String,
+    translation: String,
+}
+
+/// Source text, context hash, locale and model.
+type Key = (String, String, String, String);
+
+/// Translations remembered from earlier runs, with hit and miss counts for
+/// the current one.
+pub struct TranslationMemory {
+    path: PathBuf,
+    entries: HashMap<Key, String>,
+    file: Option<File>,
+    hits: usize,
+    misses: usize,
+}
+
+impl TranslationMemory {
+    /// Load the memory stored at `path`, starting empty when it does not exist.
+    /// Lines that cannot be parsed are skipped with a warning.
+    pub fn open(path: &Path) -> Result<Self> {
+        trace!("TranslationMemory::open path={}", path.display());
+        let mut entries = HashMap::new();
+        if path.exists() {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("cannot read translation memory {}", path.display()))?;
+            for (n, line) in text.lines().enumerate() {
+                match serde_json::from_str::<Entry>(line) {
+                    Ok(e) => {
+                        entries.insert((e.text, e.context, e.locale, e.model), e.translation);
+                    }
+                    Err(err) => warn!("{}:{}: skipping entry: {err}", path.display(), n + 1),
+                }
+            }
+        }
+        debug!("loaded {} remembered translations", entries.len());
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+            file: None,
+            hits: 0,
+            misses: 0,
+        })
+    }
+
+    /// Default memory file: `subtra/translation_memory.jsonl` in the user's cache
+    /// directory (`$XDG_CACHE_HOME`, `~/.cache` or `%LOCALAPPDATA%`).
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+        Some(dir.join("subtra").join("translation_memory.jsonl"))
+    }
+
+    /// Look up the translation of `text` following the line `context`,
+    /// counting a hit or a miss.
+    pub fn get(&mut self, text: &str, context: &str, locale: &str, model: &str) -> Option<String> {
+        let found = self
+            .entries
+            .get(&key(text, context, locale, model))
+            .cloned();
+        match found {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        found
+    }
+
+    /// Remember `translation` and append it to the memory file.
+    pub fn insert(
+        &mut self,
+        text: &str,
+        context: &str,
+        locale: &str,
+        model: &str,
+        translation: &str,
+    ) -> Result<()> {
+        let key = key(text, context, locale, model);
+        if self.entries.get(&key).map(String::as_str) == Some(translation) {
+            return Ok(());
+        }
+        let entry = Entry {
+            text: key.0.clone(),
+            context: key.1.clone(),
+            locale: key.2.clone(),
+            model: key.3.clone(),
+            translation: translation.to_string(),
+        };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.append(&line)?;
+        self.entries.insert(key, entry.translation);
+        Ok(())
+    }
+
+    /// Lookups answered from memory so far.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Lookups that had to go to the backend so far.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Append `line` to the memory file, creating it and its directory first.
+    /// The line is written in one call so concurrent runs do not interleave.
+    fn append(&mut self, line: &str) -> Result<()> {
+        if self.file.is_none() {
+            if let Some(dir) = self.path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .with_context(|| {
+                    format!("cannot write translation memory {}", self.path.display())
+                })?;
+            self.file = Some(file);
+        }
+        if let Some(file) = &mut self.file {
+            file.write_all(line.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Build the lookup key, trimming `text` and hashing the normalized `context`.
+fn key(text: &str, context: &str, locale: &str, model: &str) -> Key {
+    (
+        text.trim().to_string(),
+        context_hash(context),
+        locale.to_string(),
+        model.to_string(),
+    )
+}
+
+/// Hash `context` after lowercasing it and reducing it to its words, so case,
+/// punctuation and line breaks do not matter.
+/// FNV-1a is used because the hash is stored and must not change between builds.
+fn context_hash(context: &str) -> String {
+    let normalized = context
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in normalized.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Ensure translations survive a reopen, keys include every part, and
+    /// hits and misses are counted.
+    #[test]
+    fn remembers_translations() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("cache").join("memory.jsonl");
+        let mut memory = TranslationMemory::open(&path).unwrap();
+        memory
+            .insert(
+                "Thank you.",
+                "Here.",
+                "pt-BR",
+                "openai/gpt-5-nano",
+                "Obrigado.",
+            )
+            .unwrap();
+        fs::write(
+            &path,
+            format!("{}not json\n", fs::read_to_string(&path).unwrap()),
+        )
+        .unwrap();
+
+        let mut memory = TranslationMemory::open(&path).unwrap();
+        assert_eq!(
+            memory.get(" Thank you.", "here", "pt-BR", "openai/gpt-5-nano"),
+            Some("Obrigado.".to_string())
+        );
+        assert_eq!(
+            memory.get("Thank you.", "There.", "pt-BR", "openai/gpt-5-nano"),
+            None
+        );
+        assert_eq!(
+            memory.get("Thank you.", "Here.", "es", "openai/gpt-5-nano"),
+            None
+        );
+        assert_eq!(memory.get("Thank you.", "Here.", "pt-BR", "deepl"), None);
+        assert_eq!((memory.hits(), memory.misses()), (1, 3));
+    }
+
+    /// Ensure context hashing ignores case, punctuation and spacing.
+    #[test]
+    fn normalizes_context() {
+        assert_eq!(context_hash("Wait -- WHAT?!\n"), context_hash("wait what"));
+        assert_ne!(context_hash("wait what"), context_hash("wait, who"));
+        assert_eq!(context_hash(""), "cbf29ce484222325");
+    }
+}
@@ -1,16 +1,89 @@
 //! Translation orchestration utilities.
-//! This module wires subtitle parsing, OpenAI calls and output writing.
+//! This module wires subtitle parsing, backend calls and output writing.
+//! The pipeline is async; the blocking functions run it on a shared private runtime.
 
-use crate::{srt, video};
-use anyhow::Result;
-use std::collections::HashMap;
+use crate::subtitle::{Cue, SubtitleDocument, SubtitleFormat};
+use crate::{encoding, locale, video};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures_util::stream::{self, StreamExt};
+use memory::TranslationMemory;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::future::Future;
+use std::io::Write;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use tracing::{debug, info, trace};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+use tracing::{debug, info, trace, warn};
 
 /// Default number of subtitle lines translated per batch.
 pub const DEFAULT_BATCH_SIZE: usize = 50;
 
+/// Default number of subtitle lines per input the glossary is built from.
+pub const DEFAULT_GLOSSARY_SAMPLE: usize = 40;
+
+/// Default estimated tokens of source text per input sent to build the glossary.
+pub const DEFAULT_GLOSSARY_TOKEN_BUDGET: usize = 1500;
+
+/// Number of preceding source lines sent as context with each batch.
+const CONTEXT_LINES: usize = 4;
+
+/// How many times lines missing from a batch answer are requested again.
+const MISSING_LINE_RETRIES: usize = 2;
+
+/// Options shared by every target locale in a `process_file` run.
+#[derive(Debug, Clone)]
+pub struct ProcessOptions {
+    /// Language of the subtitle track to extract from videos, e.g. `en` or `jpn`.
+    pub source_language: String,
+    /// How to choose the subtitle stream when the input is a video.
+    pub stream: video::StreamSelection,
+    /// Number of subtitle lines to translate per batch.
+    /// With `token_budget` set this is only an upper bound.
+    pub batch_size: usize,
+    /// Estimated tokens of source text per batch; batches are packed by count
+    /// of lines when `None`.
+    pub token_budget: Option<usize>,
+    /// Number of batches translated at the same time.
+    pub concurrency: usize,
+    /// Encoding label of subtitle inputs, e.g. `windows-1252`; detected when `None`.
+    pub input_encoding: Option<String>,
+    /// Encoding label of the translated files; UTF-8 when `None`.
+    pub output_encoding: Option<String>,
+    /// Translation memory file consulted before each batch; none when `None`.
+    pub memory: Option<PathBuf>,
+    /// Glossary file merged with the generated glossary and written back;
+    /// the glossary only lives for the run when `None`.
+    pub glossary: Option<PathBuf>,
+    /// Number of lines of each input the glossary is built from.
+    pub glossary_sample: usize,
+    /// Estimated tokens of the glossary sample of each input.
+    pub glossary_token_budget: usize,
+}
+
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        Self {
+            source_language: locale::DEFAULT_SOURCE_LANGUAGE.to_string(),
+            stream: video::StreamSelection::default(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            token_budget: None,
+            concurrency: 1,
+            input_encoding: None,
+            output_encoding: None,
+            memory: None,
+            glossary: None,
+            glossary_sample: DEFAULT_GLOSSARY_SAMPLE,
+            glossary_token_budget: DEFAULT_GLOSSARY_TOKEN_BUDGET,
+        }
+    }
+}
+
 /// Translates a batch of lines with optional context (e.g., previous lines).
 /// Represents a single line paired with its SRT index.
 #[derive(Debug, Clone, PartialEq)]
@@ -21,178 +94,945 @@ pub struct IndexedLine {
     pub text: String,
 }
 
+/// Ways a model answer can fail to be a usable translation.
+/// Backends return these inside `anyhow::Error`, so callers can downcast them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TranslationError {
+    /// The answer was not valid JSON.
+    InvalidJson(String),
+    /// The JSON did not match the `translatedLines` schema.
+    SchemaViolation(String),
+    /// The answer was cut off by the output token limit.
+    Truncated,
+    /// The model declined to translate, with its explanation.
+    Refusal(String),
+}
+
+impl std::fmt::Display for TranslationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TranslationError::InvalidJson(e) => write!(f, "model answer is not valid JSON: {e}"),
+            TranslationError::SchemaViolation(e) => {
+                write!(f, "model answer does not match the schema: {e}")
+            }
+            TranslationError::Truncated => write!(f, "model answer was cut off at the token limit"),
+            TranslationError::Refusal(e) => write!(f, "model refused to translate: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for TranslationError {}
+
+/// Error returned when a run is stopped through its `CancellationToken`.
+/// Batches finished before that are kept in the partial file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("translation was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
 /// Translates a batch of lines with optional context (e.g., previous lines).
-pub trait Translator {
+/// This is the blocking interface. The HTTP backends implement it on top of
+/// their async calls, and a custom `Translator` is used with the pipeline by
+/// wrapping it in `Blocking`.
+pub trait Translator: Send + Sync {
     /// Translate `lines` to the target locale preserving line boundaries.
+    /// `summary` is the glossary formatted by `Glossary::to_prompt` for
+    /// `target_locale`.
     fn translate_batch(
         &self,
         summary: &str,
         prev: &[String],
         lines: &[IndexedLine],
+        source_language: &str,
         target_locale: &str,
     ) -> Result<Vec<IndexedLine>>;
 
-    /// Build a glossary and summary from a sample of lines.
-    fn build_glossary(&self, sample: &[String]) -> Result<String>;
+    /// Build a glossary of terms and characters from a sample of lines.
+    /// The same glossary is shared by every locale in `target_locales`.
+    fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary>;
+
+    /// Name of the service and model, e.g. `openai/gpt-5-nano`, keeping
+    /// translation memory entries of different models apart. It is stored in
+    /// the memory file, so it must stay the same between builds and versions.
+    fn model(&self) -> String;
 }
 
+impl<T: Translator + ?Sized> Translator for Box<T> {
+    fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        (**self).translate_batch(summary, prev, lines, source_language, target_locale)
+    }
+
+    fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        (**self).build_glossary(sample, target_locales)
+    }
+
+    fn model(&self) -> String {
+        (**self).model()
+    }
+}
+
+/// Async counterpart of `Translator`, implemented by the HTTP backends.
+/// Several batches of one run may be awaited at the same time.
+#[async_trait]
+pub trait AsyncTranslator: Send + Sync {
+    /// Translate `lines` to the target locale preserving line boundaries.
+    /// `summary` is the glossary formatted by `Glossary::to_prompt` for
+    /// `target_locale`.
+    async fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>>;
+
+    /// Build a glossary of terms and characters from a sample of lines.
+    /// The same glossary is shared by every locale in `target_locales`.
+    async fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary>;
+
+    /// Name of the service and model, e.g. `openai/gpt-5-nano`, keeping
+    /// translation memory entries of different models apart. It is stored in
+    /// the memory file, so it must stay the same between builds and versions.
+    fn model(&self) -> String;
+}
+
+/// Adapter using a blocking `Translator` as an `AsyncTranslator`.
+/// Every call runs on tokio's blocking thread pool, so a slow translator does
+/// not stall the other tasks of the runtime. This is how a sync translator is
+/// passed to `process_file` and the other pipeline functions.
+pub struct Blocking<T>(Arc<T>);
+
+impl<T> Blocking<T> {
+    /// Wrap `translator`.
+    pub fn new(translator: T) -> Self {
+        Self(Arc::new(translator))
+    }
+
+    /// The wrapped translator.
+    pub fn get_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl<T: Translator + 'static> AsyncTranslator for Blocking<T> {
+    async fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        let translator = self.0.clone();
+        let (summary, prev, lines) = (summary.to_string(), prev.to_vec(), lines.to_vec());
+        let (source_language, target_locale) =
+            (source_language.to_string(), target_locale.to_string());
+        run_blocking(move || {
+            translator.translate_batch(&summary, &prev, &lines, &source_language, &target_locale)
+        })
+        .await?
+    }
+
+    async fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        let translator = self.0.clone();
+        let sample = sample.to_vec();
+        let target_locales: Vec<String> = target_locales.iter().map(|l| l.to_string()).collect();
+        run_blocking(move || {
+            let target_locales: Vec<&str> = target_locales.iter().map(String::as_str).collect();
+            translator.build_glossary(&sample, &target_locales)
+        })
+        .await?
+    }
+
+    fn model(&self) -> String {
+        self.0.model()
+    }
+}
+
+#[async_trait]
+impl AsyncTranslator for Box<dyn AsyncTranslator> {
+    async fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        (**self)
+            .translate_batch(summary, prev, lines, source_language, target_locale)
+            .await
+    }
+
+    async fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        (**self).build_glossary(sample, target_locales).await
+    }
+
+    fn model(&self) -> String {
+        (**self).model()
+    }
+}
+
+/// State shared by every locale of one run.
+struct Session<'a> {
+    /// Stops the run when triggered.
+    cancel: &'a CancellationToken,
+    /// Translations looked up before and stored after each batch.
+    memory: Option<TranslationMemory>,
+}
+
+pub mod anthropic;
+pub mod backend;
+mod batch;
+pub mod deepl;
+pub mod echo;
+pub mod glossary;
+mod http;
+pub mod libretranslate;
+pub mod memory;
+pub mod ollama;
 pub mod openai;
+mod prompt;
+mod sample;
+
+pub use glossary::{Character, Glossary, Term};
+pub use http::RetryPolicy;
+pub use tokio_util::sync::CancellationToken;
+
+/// Runtime shared by every call of the blocking API. It lives for the whole
+/// process, so connections pooled by a backend's HTTP client during one call
+/// are still usable in the next.
+static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+
+thread_local! {
+    /// Set while `Blocking` runs a sync translator on tokio's blocking pool.
+    /// Those threads see the caller's runtime but are allowed to block.
+    static IN_BLOCKING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `future` to completion on the shared blocking runtime.
+/// The blocking API is built on this. Called from async code it would panic
+/// or stall the caller's runtime, so it returns an error there instead,
+/// unless the caller is a `Blocking` call on the blocking thread pool.
+fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    if tokio::runtime::Handle::try_current().is_ok() && !IN_BLOCKING.get() {
+        return Err(anyhow!(
+            "the blocking API cannot be called from async code, use the async functions"
+        ));
+    }
+    let runtime = match RUNTIME.get() {
+        Some(runtime) => runtime,
+        None => {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            RUNTIME.get_or_init(|| runtime)
+        }
+    };
+    Ok(runtime.block_on(future))
+}
+
+/// Run `f` on tokio's blocking thread pool, marking the thread so the
+/// blocking API can be used from it.
+async fn run_blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> Result<T> {
+    Ok(tokio::task::spawn_blocking(move || {
+        IN_BLOCKING.set(true);
+        let out = f();
+        IN_BLOCKING.set(false);
+        out
+    })
+    .await?)
+}
+
+/// Await `future` unless `cancel` fires first, in which case it is dropped
+/// and `Cancelled` is returned.
+async fn cancellable<T>(
+    cancel: &CancellationToken,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::select! {
+        biased;
+        _ = cancel.cancelled() => Err(Cancelled.into()),
+        result = future => result,
+    }
+}
 
-/// Process a video file or existing SRT by extracting or reading English
-/// subtitles and translating them.
+/// Process a video file or existing SRT by extracting or reading the source
+/// subtitles and translating them to `target_locale`.
 /// This function should output the translated SRT alongside the input file.
+/// It blocks until done; async callers should use `process_file_async`.
+/// A sync `Translator` is passed wrapped in `Blocking`.
 pub fn process_file(
     input: &Path,
-    translator: &impl Translator,
-    batch_size: usize,
+    translator: &(impl AsyncTranslator + ?Sized),
+    target_locale: &str,
+    options: &ProcessOptions,
+) -> Result<PathBuf> {
+    block_on(process_file_async(
+        input,
+        translator,
+        target_locale,
+        options,
+        &CancellationToken::new(),
+    ))?
+}
+
+/// Blocking version of `process_file_multi_async`.
+pub fn process_file_multi(
+    input: &Path,
+    translator: &(impl AsyncTranslator + ?Sized),
+    target_locales: &[&str],
+    options: &ProcessOptions,
+) -> Result<Vec<PathBuf>> {
+    block_on(process_file_multi_async(
+        input,
+        translator,
+        target_locales,
+        options,
+        &CancellationToken::new(),
+    ))?
+}
+
+/// Blocking version of `build_series_glossary_async`.
+pub fn build_series_glossary(
+    inputs: &[PathBuf],
+    translator: &(impl AsyncTranslator + ?Sized),
+    target_locales: &[&str],
+    options: &ProcessOptions,
+) -> Result<Glossary> {
+    block_on(build_series_glossary_async(
+        inputs,
+        translator,
+        target_locales,
+        options,
+        &CancellationToken::new(),
+    ))?
+}
+
+/// Build one glossary for every episode of a season in `inputs`.
+/// The way this works is by reading the source subtitles of each input,
+/// taking a sample from every one of them and asking the model for a single
+/// glossary over the combined sample. When `options.glossary` is set the
+/// result is merged into that file and written back, so the command can be
+/// run again as new episodes come out.
+pub async fn build_series_glossary_async(
+    inputs: &[PathBuf],
+    translator: &(impl AsyncTranslator + ?Sized),
+    target_locales: &[&str],
+    options: &ProcessOptions,
+    cancel: &CancellationToken,
+) -> Result<Glossary> {
+    trace!(
+        "build_series_glossary inputs={} target_locales={:?}",
+        inputs.len(),
+        target_locales
+    );
+    if inputs.is_empty() {
+        return Err(anyhow!("no input given"));
+    }
+    let locales = target_locales
+        .iter()
+        .map(|tag| locale::resolve(tag).map(|l| l.tag))
+        .collect::<Result<Vec<_>>>()?;
+    let mut sample = Vec::new();
+    for input in inputs {
+        let source = read_source(input, options, cancel).await?;
+        sample.extend(sample::glossary_sample(
+            &source.doc.cues,
+            options.glossary_sample,
+            options.glossary_token_budget,
+        ));
+        if let Some(t) = source.temp {
+            fs::remove_file(t)?;
+        }
+    }
+    info!(
+        "building glossary from {} line(s) of {} input(s)",
+        sample.len(),
+        inputs.len()
+    );
+    let glossary = cancellable(cancel, translator.build_glossary(&sample, &locales)).await?;
+    match &options.glossary {
+        Some(path) => save_glossary(path, &glossary),
+        None => Ok(glossary),
+    }
+}
+
+/// Async version of `process_file` that stops with `Cancelled` once `cancel`
+/// is triggered.
+pub async fn process_file_async(
+    input: &Path,
+    translator: &(impl AsyncTranslator + ?Sized),
+    target_locale: &str,
+    options: &ProcessOptions,
+    cancel: &CancellationToken,
 ) -> Result<PathBuf> {
-    trace!("process_file input={}", input.display());
-    // Detect whether the input is already an SRT file so we skip extraction.
-    let is_srt = input
+    let mut outputs =
+        process_file_multi_async(input, translator, &[target_locale], options, cancel).await?;
+    Ok(outputs.remove(0))
+}
+
+/// Process a video file or existing SRT once and translate it to every locale
+/// in `target_locales`.
+/// The way this works is by extracting and parsing the source subtitles and
+/// building the glossary a single time, then running the batch loop per locale
+/// with its own partial-progress file. Output paths are returned in the same
+/// order as the locales. The glossary file in `options.glossary` is only
+/// updated once every locale is written.
+/// When `cancel` is triggered the requests in flight are dropped and
+/// `Cancelled` is returned; finished batches stay in the partial file, so a
+/// later run resumes from there.
+pub async fn process_file_multi_async(
+    input: &Path,
+    translator: &(impl AsyncTranslator + ?Sized),
+    target_locales: &[&str],
+    options: &ProcessOptions,
+    cancel: &CancellationToken,
+) -> Result<Vec<PathBuf>> {
+    trace!(
+        "process_file_multi input={} target_locales={:?}",
+        input.display(),
+        target_locales
+    );
+    if target_locales.is_empty() {
+        return Err(anyhow!("no target locale given"));
+    }
+    let locales = target_locales
+        .iter()
+        .map(|tag| locale::resolve(tag).map(|l| l.tag))
+        .collect::<Result<Vec<_>>>()?;
+    let output_encoding = match &options.output_encoding {
+        Some(label) => encoding::resolve(label)?,
+        None => encoding_rs::UTF_8,
+    };
+    let Source {
+        doc: source_doc,
+        temp,
+    } = read_source(input, options, cancel).await?;
+    let sample = sample::glossary_sample(
+        &source_doc.cues,
+        options.glossary_sample,
+        options.glossary_token_budget,
+    );
+    info!("building glossary from {} sampled line(s)", sample.len());
+    let generated = cancellable(cancel, translator.build_glossary(&sample, &locales)).await?;
+    info!(
+        "glossary built with {} term(s) and {} character(s)",
+        generated.terms.len(),
+        generated.characters.len()
+    );
+    let glossary = match &options.glossary {
+        Some(path) => merge_glossary(path, &generated)?,
+        None => generated.clone(),
+    };
+
+    let mut session = Session {
+        cancel,
+        memory: options
+            .memory
+            .as_deref()
+            .map(TranslationMemory::open)
+            .transpose()?,
+    };
+    let mut outputs = Vec::new();
+    let mut untranslated = Vec::new();
+    for tag in &locales {
+        info!("translating to {tag}");
+        let partial_path = input.with_file_name(format!(
+            "{}_partial_translation_{}",
+            input.file_stem().unwrap_or_default().to_string_lossy(),
+            locale::file_suffix(tag)
+        ));
+        let (cues, missing) = translate_cues(
+            translator,
+            &source_doc.cues,
+            &glossary,
+            tag,
+            options,
+            &partial_path,
+            &mut session,
+        )
+        .await?;
+        let out_path = output_path(input, source_doc.format.extension(), tag);
+        info!("writing output to {}", out_path.display());
+        let out_content = source_doc.with_cues(cues).format();
+        fs::write(&out_path, encoding::encode(&out_content, output_encoding)?)?;
+        if partial_path.exists() {
+            info!("removing partial translation {}", partial_path.display());
+            fs::remove_file(&partial_path)?;
+        }
+        info!("wrote {}", out_path.display());
+        outputs.push(out_path);
+        untranslated.push((tag, missing));
+    }
+    if let Some(path) = &options.glossary {
+        save_glossary(path, &generated)?;
+    }
+    if let Some(t) = temp {
+        info!("removing temporary file");
+        fs::remove_file(t)?;
+    }
+    for (tag, missing) in &untranslated {
+        if !missing.is_empty() {
+            let list: Vec<String> = missing.iter().map(|i| i.to_string()).collect();
+            warn!(
+                "{tag}: {} line(s) left untranslated: {}",
+                missing.len(),
+                list.join(", ")
+            );
+        }
+    }
+    if untranslated.iter().all(|(_, m)| m.is_empty()) {
+        info!("every line was translated");
+    }
+    if let Some(memory) = &session.memory {
+        info!(
+            "translation memory: {} hit(s), {} miss(es)",
+            memory.hits(),
+            memory.misses()
+        );
+    }
+    Ok(outputs)
+}
+
+/// Source subtitles of one input.
+struct Source {
+    /// Parsed subtitles.
+    doc: SubtitleDocument,
+    /// Subtitles extracted from a video, removed once the run is done.
+    temp: Option<PathBuf>,
+}
+
+/// Read the source subtitles of `input`, extracting them first when it is a video.
+/// Subtitle files are read directly, so extraction is skipped for them.
+async fn read_source(
+    input: &Path,
+    options: &ProcessOptions,
+    cancel: &CancellationToken,
+) -> Result<Source> {
+    let language = locale::resolve_language(&options.source_language)?;
+    let ext = input
         .extension()
-        .map(|e| e.eq_ignore_ascii_case("srt"))
-        .unwrap_or(false);
-    let (content, temp) = if is_srt {
-        info!("reading English subtitles");
-        (fs::read_to_string(input)?, None)
-    } else {
-        info!("extracting English subtitles");
-        let extracted = video::extract_english_subtitles(input)?;
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if SubtitleFormat::from_extension(&ext).is_none() {
+        info!("extracting {} subtitles", language.name);
+        let (path, stream) = (input.to_path_buf(), options.stream.clone());
+        let extracted = cancellable(cancel, async move {
+            tokio::task::spawn_blocking(move || {
+                video::extract_subtitles_with(&path, language.iso1, &stream)
+            })
+            .await?
+        })
+        .await?;
         let temp = input.with_file_name(format!(
-            "{}_temp_en.srt",
-            input.file_stem().unwrap_or_default().to_string_lossy()
+            "{}_temp_{}.{}",
+            input.file_stem().unwrap_or_default().to_string_lossy(),
+            language.iso1,
+            extracted.extension().unwrap_or_default().to_string_lossy()
         ));
         fs::rename(&extracted, &temp)?;
-        (fs::read_to_string(&temp)?, Some(temp))
-    };
-    let english_blocks = srt::parse(&content)?;
+        Ok(Source {
+            doc: SubtitleDocument::read(&temp)?,
+            temp: Some(temp),
+        })
+    } else {
+        info!("reading {} subtitles", language.name);
+        let encoding = options
+            .input_encoding
+            .as_deref()
+            .map(encoding::resolve)
+            .transpose()?;
+        Ok(Source {
+            doc: SubtitleDocument::read_with(input, encoding)?,
+            temp: None,
+        })
+    }
+}
 
-    let mut sample = Vec::new();
-    for block in &english_blocks {
-        for line in &block.text {
-            if sample.len() >= 15 {
-                break;
+/// Merge `generated` into the glossary file at `path` without writing it.
+/// Returns the merged glossary, which is what the prompts should use.
+fn merge_glossary(path: &Path, generated: &Glossary) -> Result<Glossary> {
+    let mut glossary = Glossary::load(path)?;
+    glossary.merge(generated);
+    Ok(glossary)
+}
+
+/// Merge `generated` into the glossary file at `path` and write it back.
+/// This function should only be called once a run succeeded. The file is
+/// read again right before writing, so terms saved by other runs in the
+/// meantime are kept.
+fn save_glossary(path: &Path, generated: &Glossary) -> Result<Glossary> {
+    let glossary = merge_glossary(path, generated)?;
+    glossary.save(path)?;
+    info!("saved glossary to {}", path.display());
+    Ok(glossary)
+}
+
+/// A batch of cues waiting to be translated.
+struct Job {
+    /// Positions of the batch in the cue list.
+    range: Range<usize>,
+    /// Lines of the batch not translated by an earlier run.
+    lines: Vec<IndexedLine>,
+    /// Source lines preceding the batch, sent as context.
+    history: Vec<String>,
+}
+
+/// Translate `source_cues` to `target_locale` batch by batch.
+/// This function should resume from and keep updating the partial file at
+/// `partial_path` so an interrupted run can continue later. It works on
+/// format-neutral cues, so every subtitle format shares this loop.
+/// The way this works is by awaiting up to `options.concurrency` pending
+/// batches at a time; each batch takes its context from the source lines
+/// before it, so batches do not wait for each other, and answers are merged
+/// and saved in whatever order they arrive. Translated lines that miss a
+/// glossary term are logged as warnings.
+/// Returns the cues and the indices that stayed untranslated.
+async fn translate_cues(
+    translator: &(impl AsyncTranslator + ?Sized),
+    source_cues: &[Cue],
+    glossary: &Glossary,
+    target_locale: &str,
+    options: &ProcessOptions,
+    partial_path: &Path,
+    session: &mut Session<'_>,
+) -> Result<(Vec<Cue>, Vec<u32>)> {
+    trace!(
+        "translate_cues target_locale={} cues={}",
+        target_locale,
+        source_cues.len()
+    );
+    let (mut blocks, mut done) = load_partial(source_cues, partial_path)?;
+    let total = blocks.len();
+    if !done.is_empty() {
+        let progress = done.len() * 100 / total;
+        info!("resuming at {progress}%");
+    }
+
+    let model = translator.model();
+    let summary = &glossary.to_prompt(target_locale);
+    if let Some(memory) = &mut session.memory {
+        for (pos, cue) in source_cues.iter().enumerate() {
+            if done.contains(&cue.index) || cue.text.iter().all(|l| l.trim().is_empty()) {
+                continue;
+            }
+            let context = preceding_text(source_cues, pos);
+            if let Some(text) = memory.get(&cue.text.join("\n"), &context, target_locale, &model) {
+                blocks[pos].text = text.lines().map(|s| s.to_string()).collect();
+                done.insert(cue.index);
             }
-            sample.push(line.clone());
-        }
-        if sample.len() >= 15 {
-            break;
         }
     }
-    info!("building glossary from sample");
-    let summary = translator.build_glossary(&sample)?;
-    info!("glossary built");
 
-    let partial_path = input.with_file_name(format!(
-        "{}_partial_translation_pt_br",
-        input.file_stem().unwrap_or_default().to_string_lossy()
-    ));
-    let (mut blocks, mut idx, mut history) = load_partial(&english_blocks, &partial_path)?;
-    let total = blocks.len();
-    if idx > 0 {
-        let done = idx * 100 / total;
-        info!("resuming at {done}%");
+    let jobs: Vec<Job> = batch::plan(source_cues, options.batch_size, options.token_budget)
+        .into_iter()
+        .filter_map(|range| {
+            let lines: Vec<IndexedLine> = source_cues[range.clone()]
+                .iter()
+                .filter(|c| !done.contains(&c.index))
+                .map(|c| IndexedLine {
+                    index: c.index,
+                    text: c.text.join("\n"),
+                })
+                .collect();
+            if lines.is_empty() {
+                return None;
+            }
+            let history = source_cues[range.start.saturating_sub(CONTEXT_LINES)..range.start]
+                .iter()
+                .map(|c| c.text.join("\n"))
+                .collect();
+            Some(Job {
+                range,
+                lines,
+                history,
+            })
+        })
+        .collect();
+    let workers = options.concurrency.clamp(1, jobs.len().max(1));
+    // Average lines per batch, which the ETA uses to count the batches left.
+    let batch_size = (jobs.iter().map(|j| j.lines.len()).sum::<usize>() / jobs.len().max(1)).max(1);
+    if workers > 1 {
+        info!("translating {} batches with {workers} workers", jobs.len());
     }
 
+    // Jobs are passed by position: a closure taking `&Job` would make the
+    // future lose `Send` to a higher-ranked lifetime limitation.
+    // Once a batch fails, the jobs not started yet are skipped, while those in
+    // flight finish and are saved before the error is returned.
+    let jobs = &jobs;
+    let failed = &AtomicBool::new(false);
+    let mut batches = stream::iter(0..jobs.len())
+        .map(|n| async move {
+            if failed.load(Ordering::Relaxed) {
+                return None;
+            }
+            let job = &jobs[n];
+            info!(
+                "translating lines {}-{} of {}",
+                job.range.start + 1,
+                job.range.end,
+                total
+            );
+            let start = Instant::now();
+            let result = translate_complete(
+                translator,
+                summary,
+                &job.history,
+                &job.lines,
+                &options.source_language,
+                target_locale,
+            )
+            .await;
+            Some((job, start.elapsed().as_millis(), result))
+        })
+        .buffer_unordered(workers);
+
     let mut last_ms: Option<u128> = None;
-    while idx < blocks.len() {
-        let end = (idx + batch_size).min(blocks.len());
-        let progress = end * 100 / total;
+    let mut untranslated = Vec::new();
+    let mut error = None;
+    while let Some(finished) =
+        cancellable(session.cancel, async { Ok(batches.next().await) }).await?
+    {
+        let Some((job, elapsed, result)) = finished else {
+            continue;
+        };
+        let mut map = match result {
+            Ok(map) => map,
+            Err(err) => {
+                if error.is_none() && jobs.len() > 1 {
+                    warn!("{err}; waiting for the batches in flight to finish");
+                }
+                failed.store(true, Ordering::Relaxed);
+                error.get_or_insert(err);
+                continue;
+            }
+        };
         info!(
-            "translating lines {}-{} of {} ({}%)",
-            idx + 1,
-            end,
-            total,
-            progress
+            "translated lines {}-{} in {} ms",
+            job.range.start + 1,
+            job.range.end,
+            elapsed
         );
-        let chunk = &mut blocks[idx..end];
-        let english: Vec<IndexedLine> = english_blocks[idx..end]
-            .iter()
-            .map(|b| IndexedLine {
-                index: b.index,
-                text: b.text.join("\n"),
-            })
-            .collect();
-        let start = std::time::Instant::now();
-        let translated = translator.translate_batch(&summary, &history, &english, "pt-BR")?;
-        let elapsed = start.elapsed().as_millis();
-        info!("translated lines {}-{} in {} ms", idx + 1, end, elapsed);
-        let mut map: HashMap<u32, String> =
-            translated.into_iter().map(|l| (l.index, l.text)).collect();
-        for block in chunk.iter_mut() {
-            if let Some(text) = map.remove(&block.index) {
-                block.text = text.lines().map(|s| s.to_string()).collect();
+        for pos in job.range.clone() {
+            let block = &mut blocks[pos];
+            if done.contains(&block.index) {
+                continue;
             }
+            match map.remove(&block.index) {
+                Some(text) => {
+                    let source = source_cues[pos].text.join("\n");
+                    for (term, expected) in glossary.violations(&source, &text, target_locale) {
+                        warn!(
+                            "{target_locale}: line {} does not use \"{}\" for \"{}\"",
+                            block.index, expected, term.source
+                        );
+                    }
+                    if let Some(memory) = &mut session.memory {
+                        let context = preceding_text(source_cues, pos);
+                        memory.insert(&source, &context, target_locale, &model, &text)?;
+                    }
+                    block.text = text.lines().map(|s| s.to_string()).collect();
+                }
+                None if block.text.iter().any(|l| !l.trim().is_empty()) => {
+                    untranslated.push(block.index);
+                    continue;
+                }
+                None => {}
+            }
+            done.insert(block.index);
         }
-        history.extend(english.into_iter().map(|l| l.text));
-        if history.len() > 4 {
-            history = history[history.len() - 4..].to_vec();
-        }
-        idx = end;
-        save_partial(&blocks, &partial_path)?;
+        save_partial(source_cues, &blocks, &done, partial_path)?;
         if let Some(prev) = last_ms {
-            let remaining = blocks.len() - idx;
-            let estimate = estimate_remaining(prev, elapsed, remaining, batch_size);
+            let remaining = total - done.len();
+            let estimate = estimate_remaining(prev, elapsed, remaining, batch_size * workers);
             info!("ETA: {}", format_eta(estimate));
         }
         last_ms = Some(elapsed);
-        let done = idx * 100 / total;
-        info!("completed {done}%");
+        let progress = done.len() * 100 / total;
+        info!("completed {progress}%");
     }
-
-    let out_path = if is_srt {
-        input.with_file_name(format!(
-            "{}_pt_br.srt",
-            input.file_stem().unwrap_or_default().to_string_lossy()
-        ))
-    } else {
-        input.with_extension("srt")
-    };
-    info!("writing output to {}", out_path.display());
-    let out_content = srt::format(&blocks);
-    fs::write(&out_path, out_content)?;
-    if let Some(t) = temp {
-        info!("removing temporary file");
-        fs::remove_file(t)?;
+    if let Some(err) = error {
+        return Err(err);
     }
-    if partial_path.exists() {
-        info!("removing partial translation {}", partial_path.display());
-        fs::remove_file(&partial_path)?;
+    untranslated.sort_unstable();
+    Ok((blocks, untranslated))
+}
+
+/// Source text of the cue before position `pos`, the context translation
+/// memory entries are keyed by; empty for the first cue.
+fn preceding_text(cues: &[Cue], pos: usize) -> String {
+    pos.checked_sub(1)
+        .map(|prev| cues[prev].text.join("\n"))
+        .unwrap_or_default()
+}
+
+/// Translate `source`, asking again for lines the answer left out.
+/// This function should compare the returned indices with the requested ones:
+/// unknown indices are ignored, and missing or blank lines are re-requested
+/// on their own up to `MISSING_LINE_RETRIES` times. Lines still missing after
+/// that are absent from the returned map.
+async fn translate_complete(
+    translator: &(impl AsyncTranslator + ?Sized),
+    summary: &str,
+    history: &[String],
+    source: &[IndexedLine],
+    source_language: &str,
+    target_locale: &str,
+) -> Result<HashMap<u32, String>> {
+    let mut done = HashMap::new();
+    let mut pending = source.to_vec();
+    for attempt in 0..=MISSING_LINE_RETRIES {
+        let wanted: HashSet<u32> = pending.iter().map(|l| l.index).collect();
+        let translated = translator
+            .translate_batch(summary, history, &pending, source_language, target_locale)
+            .await?;
+        let mut unknown = Vec::new();
+        for line in translated {
+            if !wanted.contains(&line.index) {
+                unknown.push(line.index);
+            } else if !line.text.trim().is_empty() {
+                done.entry(line.index).or_insert(line.text);
+            }
+        }
+        if !unknown.is_empty() {
+            warn!("ignoring unrequested indices {unknown:?} in the answer");
+        }
+        pending.retain(|l| !done.contains_key(&l.index) && !l.text.trim().is_empty());
+        if pending.is_empty() {
+            break;
+        }
+        let indices: Vec<u32> = pending.iter().map(|l| l.index).collect();
+        if attempt < MISSING_LINE_RETRIES {
+            warn!("answer is missing indices {indices:?}, asking again");
+        } else {
+            warn!("giving up on indices {indices:?}");
+        }
     }
-    info!("wrote {}", out_path.display());
-    Ok(out_path)
+    Ok(done)
+}
+
+/// Build the translated output path for `input` in the `tag` locale.
+/// Videos and subtitle inputs both get a `_pt_br` style suffix, so the
+/// original is kept and every locale has its own file.
+/// `ext` is the extension of the format being written.
+fn output_path(input: &Path, ext: &str, tag: &str) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    input.with_file_name(format!("{}_{}.{}", stem, locale::file_suffix(tag), ext))
+}
+
+/// Translation progress saved between runs.
+#[derive(Serialize, Deserialize)]
+struct Partial {
+    /// Every cue, translated or not.
+    cues: Vec<Cue>,
+    /// Indices of the cues that are finished.
+    done: Vec<u32>,
+    /// Source text of every cue, empty in files from older versions.
+    #[serde(default)]
+    source: Vec<String>,
 }
 
 /// Load an existing partial translation if available.
-/// This function should read a JSON file of blocks and compute the resume index and history.
-fn load_partial(
-    original: &[srt::SrtBlock],
-    path: &Path,
-) -> Result<(Vec<srt::SrtBlock>, usize, Vec<String>)> {
+/// This function should read the JSON progress file and return the cues with
+/// the set of finished indices. Files from older versions hold only the cues,
+/// translated in order, so the leading cues that differ from `original` count
+/// as finished. A file whose cue indices or source text do not match
+/// `original` is ignored.
+fn load_partial(original: &[Cue], path: &Path) -> Result<(Vec<Cue>, HashSet<u32>)> {
     trace!("load_partial path={}", path.display());
     if !path.exists() {
-        return Ok((original.to_vec(), 0, Vec::new()));
+        return Ok((original.to_vec(), HashSet::new()));
     }
     let text = fs::read_to_string(path)?;
-    let blocks: Vec<srt::SrtBlock> = serde_json::from_str(&text)?;
-    let mut idx = 0;
-    while idx < blocks.len() && blocks[idx].text != original[idx].text {
-        idx += 1;
+    let partial = match serde_json::from_str::<Partial>(&text) {
+        Ok(partial) => partial,
+        Err(_) => {
+            let cues: Vec<Cue> = serde_json::from_str(&text)?;
+            let done = cues
+                .iter()
+                .zip(original)
+                .take_while(|(cue, orig)| cue.text != orig.text)
+                .map(|(cue, _)| cue.index)
+                .collect();
+            Partial {
+                cues,
+                done,
+                source: Vec::new(),
+            }
+        }
+    };
+    if !partial_matches(&partial, original) {
+        warn!(
+            "ignoring partial translation {} made from other subtitles",
+            path.display()
+        );
+        return Ok((original.to_vec(), HashSet::new()));
+    }
+    Ok((partial.cues, partial.done.into_iter().collect()))
+}
+
+/// Whether `partial` was made from the `original` cues.
+/// The way this works is by comparing the cue indices and the source text.
+/// Files from older versions do not store the source, so only the cues not
+/// finished yet, which still hold it, are compared.
+fn partial_matches(partial: &Partial, original: &[Cue]) -> bool {
+    if partial.cues.len() != original.len()
+        || partial
+            .cues
+            .iter()
+            .zip(original)
+            .any(|(p, o)| p.index != o.index)
+    {
+        return false;
+    }
+    if !partial.source.is_empty() {
+        return partial.source.len() == original.len()
+            && partial
+                .source
+                .iter()
+                .zip(original)
+                .all(|(text, cue)| *text == cue.text.join("\n"));
     }
-    let start = idx.saturating_sub(4);
-    let history = original[start..idx]
+    let done: HashSet<u32> = partial.done.iter().copied().collect();
+    partial
+        .cues
         .iter()
-        .map(|b| b.text.join("\n"))
-        .collect();
-    Ok((blocks, idx, history))
+        .zip(original)
+        .all(|(p, o)| done.contains(&p.index) || p.text == o.text)
 }
 
 /// Save the current translation progress to disk.
-/// The way this works is by serializing the blocks to JSON for later resumption.
-fn save_partial(blocks: &[srt::SrtBlock], path: &Path) -> Result<()> {
+/// The way this works is by serializing a `Partial` with the cues, finished
+/// indices and source text to JSON, the same type `load_partial` reads back.
+fn save_partial(original: &[Cue], blocks: &[Cue], done: &HashSet<u32>, path: &Path) -> Result<()> {
     trace!("save_partial path={}", path.display());
-    let text = serde_json::to_string(blocks)?;
-    fs::write(path, text)?;
+    let mut done: Vec<u32> = done.iter().copied().collect();
+    done.sort_unstable();
+    let partial = Partial {
+        cues: blocks.to_vec(),
+        done,
+        source: original.iter().map(|c| c.text.join("\n")).collect(),
+    };
+    let mut file = std::io::BufWriter::new(fs::File::create(path)?);
+    serde_json::to_writer(&mut file, &partial)?;
+    file.flush()?;
     debug!("saved partial translation to {}", path.display());
     Ok(())
 }
@@ -209,7 +1049,7 @@ fn estimate_remaining(prev_ms: u128, curr_ms: u128, remaining: usize, batch: usi
         batch
     );
     let avg = (prev_ms + curr_ms) / 2;
-    let batches = (remaining + batch - 1) / batch;
+    let batches = remaining.div_ceil(batch);
     avg * batches as u128
 }
 
@@ -237,34 +1077,430 @@ fn format_eta(ms: u128) -> String {
 mod tests {
     use super::*;
     use std::fs;
+    use std::sync::atomic::AtomicUsize;
     use tempfile::tempdir;
 
-    /// Ensure we can load a partial file and resume from the correct index.
+    /// Ensure lines missing from an answer are requested again, and lines that
+    /// never come back are reported instead of failing the run.
+    #[tokio::test]
+    async fn rerequests_missing_lines() {
+        use std::sync::Mutex;
+
+        /// Drop index 2 from the first answer and index 3 from every answer.
+        struct ForgetfulTr {
+            requests: Mutex<Vec<Vec<u32>>>,
+        }
+        impl Translator for ForgetfulTr {
+            fn build_glossary(&self, _sample: &[String], _locales: &[&str]) -> Result<Glossary> {
+                Ok(Glossary::default())
+            }
+
+            fn translate_batch(
+                &self,
+                _summary: &str,
+                _prev: &[String],
+                lines: &[IndexedLine],
+                _source_language: &str,
+                _target_locale: &str,
+            ) -> Result<Vec<IndexedLine>> {
+                let mut requests = self.requests.lock().unwrap();
+                let first = requests.is_empty();
+                requests.push(lines.iter().map(|l| l.index).collect());
+                let mut out: Vec<IndexedLine> = lines
+                    .iter()
+                    .filter(|l| l.index != 3 && !(first && l.index == 2))
+                    .map(|l| IndexedLine {
+                        index: l.index,
+                        text: format!("pt:{}", l.text),
+                    })
+                    .collect();
+                out.push(IndexedLine {
+                    index: 99,
+                    text: "invented".into(),
+                });
+                Ok(out)
+            }
+
+            fn model(&self) -> String {
+                "test".to_string()
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let cues: Vec<Cue> = (1..=3)
+            .map(|i| Cue {
+                index: i,
+                start_ms: 0,
+                end_ms: 1000,
+                text: vec![format!("line {i}")],
+                style: None,
+                position: None,
+            })
+            .collect();
+        let tr = Blocking::new(ForgetfulTr {
+            requests: Mutex::new(Vec::new()),
+        });
+        let partial = dir.path().join("partial");
+        let options = ProcessOptions {
+            batch_size: 10,
+            ..ProcessOptions::default()
+        };
+        let cancel = CancellationToken::new();
+        let mut session = Session {
+            cancel: &cancel,
+            memory: None,
+        };
+        let (out, missing) = translate_cues(
+            &tr,
+            &cues,
+            &Glossary::default(),
+            "pt-BR",
+            &options,
+            &partial,
+            &mut session,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            *tr.get_ref().requests.lock().unwrap(),
+            vec![vec![1, 2, 3], vec![2, 3], vec![3]]
+        );
+        assert_eq!(out[1].text, vec!["pt:line 2"]);
+        assert_eq!(out[2].text, vec!["line 3"]);
+        assert_eq!(missing, vec![3]);
+    }
+
+    /// Ensure we can load a partial file and know which cues are finished, for
+    /// both the current format and the cue list older versions saved.
     #[test]
     fn resumes_from_partial() {
         let blocks = vec![
-            srt::SrtBlock {
+            Cue {
                 index: 1,
                 start_ms: 0,
                 end_ms: 1000,
                 text: vec!["a".into()],
+                style: None,
+                position: None,
             },
-            srt::SrtBlock {
+            Cue {
                 index: 2,
                 start_ms: 1000,
                 end_ms: 2000,
                 text: vec!["b".into()],
+                style: None,
+                position: None,
             },
         ];
         let dir = tempdir().unwrap();
         let partial = dir.path().join("video_partial_translation_pt_br");
         let mut translated = blocks.clone();
+        translated[1].text = vec!["pt:b".into()];
+        save_partial(&blocks, &translated, &HashSet::from([2]), &partial).unwrap();
+        let (loaded, done) = load_partial(&blocks, &partial).unwrap();
+        assert_eq!(done, HashSet::from([2]));
+        assert_eq!(loaded[1].text, vec!["pt:b".to_string()]);
+
         translated[0].text = vec!["pt:a".into()];
-        save_partial(&translated, &partial).unwrap();
-        let (loaded, idx, history) = load_partial(&blocks, &partial).unwrap();
-        assert_eq!(idx, 1);
-        assert_eq!(history, vec!["a".to_string()]);
+        translated[1].text = vec!["b".into()];
+        fs::write(&partial, serde_json::to_string(&translated).unwrap()).unwrap();
+        let (loaded, done) = load_partial(&blocks, &partial).unwrap();
+        assert_eq!(done, HashSet::from([1]));
         assert_eq!(loaded[0].text, vec!["pt:a".to_string()]);
+
+        let (loaded, done) = load_partial(&blocks[..1], &partial).unwrap();
+        assert!(done.is_empty());
+        assert_eq!(loaded[0].text, vec!["a".to_string()]);
+
+        let mut edited = blocks.clone();
+        edited[0].text = vec!["c".into()];
+        save_partial(&blocks, &translated, &HashSet::from([1]), &partial).unwrap();
+        let (loaded, done) = load_partial(&edited, &partial).unwrap();
+        assert!(done.is_empty());
+        assert_eq!(loaded, edited);
+        let mut renumbered = blocks.clone();
+        renumbered[1].index = 3;
+        let (_, done) = load_partial(&renumbered, &partial).unwrap();
+        assert!(done.is_empty());
+    }
+
+    /// Ensure a failing batch returns its error only after the batches in
+    /// flight finished and were saved, and that no further batch starts.
+    #[tokio::test]
+    async fn saves_finished_batches_on_error() {
+        use std::sync::Mutex;
+        use std::time::Duration;
+
+        /// Fail the first batch soon and answer the others later.
+        struct FailingTr {
+            requested: Mutex<Vec<u32>>,
+        }
+        #[async_trait]
+        impl AsyncTranslator for FailingTr {
+            async fn build_glossary(
+                &self,
+                _sample: &[String],
+                _locales: &[&str],
+            ) -> Result<Glossary> {
+                Ok(Glossary::default())
+            }
+
+            async fn translate_batch(
+                &self,
+                _summary: &str,
+                _prev: &[String],
+                lines: &[IndexedLine],
+                _source_language: &str,
+                _target_locale: &str,
+            ) -> Result<Vec<IndexedLine>> {
+                self.requested.lock().unwrap().push(lines[0].index);
+                if lines[0].index == 1 {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    return Err(anyhow!("quota exceeded"));
+                }
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                Ok(lines.to_vec())
+            }
+
+            fn model(&self) -> String {
+                "test".to_string()
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let cues: Vec<Cue> = (1..=4)
+            .map(|i| Cue {
+                index: i,
+                start_ms: 0,
+                end_ms: 1000,
+                text: vec![format!("line {i}")],
+                style: None,
+                position: None,
+            })
+            .collect();
+        let partial = dir.path().join("partial");
+        let tr = FailingTr {
+            requested: Mutex::new(Vec::new()),
+        };
+        let options = ProcessOptions {
+            batch_size: 1,
+            concurrency: 2,
+            ..ProcessOptions::default()
+        };
+        let cancel = CancellationToken::new();
+        let mut session = Session {
+            cancel: &cancel,
+            memory: None,
+        };
+        let err = translate_cues(
+            &tr,
+            &cues,
+            &Glossary::default(),
+            "pt-BR",
+            &options,
+            &partial,
+            &mut session,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "quota exceeded");
+        assert_eq!(*tr.requested.lock().unwrap(), vec![1, 2]);
+        let (_, done) = load_partial(&cues, &partial).unwrap();
+        assert_eq!(done, HashSet::from([2]));
+    }
+
+    /// Ensure batches run in parallel, finish out of order and still land in
+    /// place, and that batches finished by an earlier run are skipped.
+    #[tokio::test]
+    async fn translates_batches_concurrently() {
+        use std::sync::Mutex;
+        use std::time::Duration;
+
+        /// Answer later batches sooner and record what was requested.
+        struct SlowTr {
+            running: AtomicUsize,
+            peak: AtomicUsize,
+            requests: Mutex<Vec<(u32, Vec<String>)>>,
+        }
+        #[async_trait]
+        impl AsyncTranslator for SlowTr {
+            async fn build_glossary(
+                &self,
+                _sample: &[String],
+                _locales: &[&str],
+            ) -> Result<Glossary> {
+                Ok(Glossary::default())
+            }
+
+            async fn translate_batch(
+                &self,
+                _summary: &str,
+                prev: &[String],
+                lines: &[IndexedLine],
+                _source_language: &str,
+                _target_locale: &str,
+            ) -> Result<Vec<IndexedLine>> {
+                let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(running, Ordering::SeqCst);
+                self.requests
+                    .lock()
+                    .unwrap()
+                    .push((lines[0].index, prev.to_vec()));
+                tokio::time::sleep(Duration::from_millis(200 - 20 * lines[0].index as u64)).await;
+                self.running.fetch_sub(1, Ordering::SeqCst);
+                Ok(lines
+                    .iter()
+                    .map(|l| IndexedLine {
+                        index: l.index,
+                        text: format!("pt:{}", l.text),
+                    })
+                    .collect())
+            }
+
+            fn model(&self) -> String {
+                "test".to_string()
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let cues: Vec<Cue> = (1..=8)
+            .map(|i| Cue {
+                index: i,
+                start_ms: 0,
+                end_ms: 1000,
+                text: vec![format!("line {i}")],
+                style: None,
+                position: None,
+            })
+            .collect();
+        let partial = dir.path().join("partial");
+        let mut earlier = cues.clone();
+        earlier[2].text = vec!["pt:line 3".into()];
+        earlier[3].text = vec!["pt:line 4".into()];
+        save_partial(&cues, &earlier, &HashSet::from([3, 4]), &partial).unwrap();
+
+        let tr = SlowTr {
+            running: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            requests: Mutex::new(Vec::new()),
+        };
+        let options = ProcessOptions {
+            batch_size: 2,
+            concurrency: 3,
+            ..ProcessOptions::default()
+        };
+        let cancel = CancellationToken::new();
+        let mut session = Session {
+            cancel: &cancel,
+            memory: None,
+        };
+        let (out, missing) = translate_cues(
+            &tr,
+            &cues,
+            &Glossary::default(),
+            "pt-BR",
+            &options,
+            &partial,
+            &mut session,
+        )
+        .await
+        .unwrap();
+        assert!(missing.is_empty());
+        for (i, cue) in out.iter().enumerate() {
+            assert_eq!(cue.text, vec![format!("pt:line {}", i + 1)]);
+        }
+        assert_eq!(tr.peak.load(Ordering::SeqCst), 3);
+        let mut requests = tr.requests.lock().unwrap().clone();
+        requests.sort();
+        let context = |range: Range<u32>| range.map(|i| format!("line {i}")).collect();
+        assert_eq!(
+            requests,
+            vec![(1, Vec::new()), (5, context(1..5)), (7, context(3..7)),]
+        );
+        let (_, done) = load_partial(&cues, &partial).unwrap();
+        assert_eq!(done.len(), 8);
+    }
+
+    /// Ensure cancelling a run stops it with `Cancelled`, keeps finished batches
+    /// in the partial file and writes no output.
+    #[tokio::test]
+    async fn cancels_running_translation() {
+        /// Translate the first batch, then cancel the run and hang.
+        struct StuckTr {
+            cancel: CancellationToken,
+        }
+        #[async_trait]
+        impl AsyncTranslator for StuckTr {
+            async fn build_glossary(
+                &self,
+                _sample: &[String],
+                _locales: &[&str],
+            ) -> Result<Glossary> {
+                Ok(Glossary::default())
+            }
+
+            async fn translate_batch(
+                &self,
+                _summary: &str,
+                _prev: &[String],
+                lines: &[IndexedLine],
+                _source_language: &str,
+                _target_locale: &str,
+            ) -> Result<Vec<IndexedLine>> {
+                if lines[0].index > 1 {
+                    self.cancel.cancel();
+                    std::future::pending::<()>().await;
+                }
+                Ok(lines.to_vec())
+            }
+
+            fn model(&self) -> String {
+                "test".to_string()
+            }
+        }
+
+        fn assert_send<T: Send>(_: &T) {}
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("orig.srt");
+        fs::write(
+            &path,
+            "1\n00:00:00,000 --> 00:00:01,000\nhello\n\n2\n00:00:01,000 --> 00:00:02,000\nworld\n",
+        )
+        .unwrap();
+        let tr = StuckTr {
+            cancel: CancellationToken::new(),
+        };
+        let options = ProcessOptions {
+            batch_size: 1,
+            ..ProcessOptions::default()
+        };
+        let run = process_file_async(&path, &tr, "pt-BR", &options, &tr.cancel);
+        assert_send(&run);
+        let err = run.await.unwrap_err();
+        assert_eq!(err.downcast::<Cancelled>().unwrap(), Cancelled);
+        assert!(!dir.path().join("orig_pt_br.srt").exists());
+        let partial = dir.path().join("orig_partial_translation_pt_br");
+        let doc = SubtitleDocument::read(&path).unwrap();
+        let (_, done) = load_partial(&doc.cues, &partial).unwrap();
+        assert_eq!(done, HashSet::from([1]));
+    }
+
+    /// Ensure the blocking API refuses to run inside a runtime instead of
+    /// panicking, and that `Blocking` runs sync translators from async code.
+    #[tokio::test]
+    async fn blocking_api_needs_no_runtime() {
+        let err = block_on(async {}).unwrap_err();
+        assert!(err.to_string().contains("async"));
+        let lines = [IndexedLine {
+            index: 1,
+            text: "hi".into(),
+        }];
+        let out = Blocking::new(MockTr)
+            .translate_batch("", &[], &lines, "en", "es")
+            .await
+            .unwrap();
+        assert_eq!(out[0].text, "pt:hi");
     }
 
     /// Verify the time estimation uses the average of the last two calls and remaining batches.
@@ -281,24 +1517,299 @@ mod tests {
         assert_eq!(format_eta(45_000), "45 seconds");
     }
 
+    /// Translator that prefixes every line with `pt:`.
+    struct MockTr;
+    impl Translator for MockTr {
+        /// Pretend to build a glossary by returning a dummy summary.
+        fn build_glossary(&self, _sample: &[String], _target_locales: &[&str]) -> Result<Glossary> {
+            Ok(Glossary {
+                summary: "sum".into(),
+                ..Glossary::default()
+            })
+        }
+
+        /// Translate by prefixing each line with `pt:` and keeping index.
+        fn translate_batch(
+            &self,
+            _summary: &str,
+            _prev: &[String],
+            lines: &[IndexedLine],
+            _source_language: &str,
+            _target_locale: &str,
+        ) -> Result<Vec<IndexedLine>> {
+            Ok(lines
+                .iter()
+                .map(|l| IndexedLine {
+                    index: l.index,
+                    text: format!("pt:{}", l.text),
+                })
+                .collect())
+        }
+
+        fn model(&self) -> String {
+            "test".to_string()
+        }
+    }
+
     /// Ensure we can translate an existing SRT file without extraction.
     #[test]
     fn translates_existing_srt() {
-        struct MockTr;
-        impl Translator for MockTr {
-            /// Pretend to build a glossary by returning a dummy summary.
-            fn build_glossary(&self, _sample: &[String]) -> Result<String> {
-                Ok("sum".into())
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("orig.srt");
+        fs::write(
+            &path,
+            "1\n00:00:00,000 --> 00:00:01,000\nhello\n\n2\n00:00:01,000 --> 00:00:02,000\nworld\n",
+        )
+        .unwrap();
+        let out = process_file(
+            &path,
+            &Blocking::new(MockTr),
+            "pt-BR",
+            &ProcessOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(out, dir.path().join("orig_pt_br.srt"));
+        let translated = fs::read_to_string(out).unwrap();
+        assert!(translated.contains("pt:hello"));
+        assert!(translated.contains("pt:world"));
+    }
+
+    /// Ensure legacy-encoded input is detected and the output encoding is honoured.
+    #[test]
+    fn converts_encodings() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("old.srt");
+        fs::write(&path, b"1\n00:00:00,000 --> 00:00:01,000\nol\xe1\n").unwrap();
+        let options = ProcessOptions {
+            output_encoding: Some("windows-1252".into()),
+            ..ProcessOptions::default()
+        };
+        let out = process_file(&path, &Blocking::new(MockTr), "pt-BR", &options).unwrap();
+        let bytes = fs::read(out).unwrap();
+        assert!(bytes.windows(6).any(|w| w == b"pt:ol\xe1"));
+    }
+
+    /// Ensure WebVTT input is translated and written back as WebVTT with its settings.
+    #[test]
+    fn translates_existing_vtt() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("orig.vtt");
+        fs::write(
+            &path,
+            "WEBVTT\n\nNOTE keep me\n\nintro\n00:00.000 --> 00:01.000 align:start\nhello\n",
+        )
+        .unwrap();
+        let out = process_file(
+            &path,
+            &Blocking::new(MockTr),
+            "pt-BR",
+            &ProcessOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(out, dir.path().join("orig_pt_br.vtt"));
+        let translated = fs::read_to_string(out).unwrap();
+        assert!(translated.starts_with("WEBVTT\n"));
+        assert!(translated.contains("NOTE keep me"));
+        assert!(translated.contains("intro\n00:00:00.000 --> 00:00:01.000 align:start\npt:hello\n"));
+    }
+
+    /// Ensure ASS input keeps styles and override tags around the translated text.
+    #[test]
+    fn translates_existing_ass() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("orig.ass");
+        fs::write(
+            &path,
+            "[Script Info]\nScriptType: v4.00+\n\n[V4+ Styles]\nFormat: Name, Fontname\nStyle: Default,Arial\n\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,{\\pos(10,20)}hello\n",
+        )
+        .unwrap();
+        let out = process_file(
+            &path,
+            &Blocking::new(MockTr),
+            "pt-BR",
+            &ProcessOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(out, dir.path().join("orig_pt_br.ass"));
+        let translated = fs::read_to_string(out).unwrap();
+        assert!(translated.contains("Style: Default,Arial"));
+        assert!(translated.contains(",,0,0,0,,{\\pos(10,20)}pt:hello\n"));
+    }
+
+    /// Ensure a multi-locale run builds the glossary once and writes one file per locale.
+    #[test]
+    fn translates_to_several_locales() {
+        struct CountingTr {
+            glossaries: AtomicUsize,
+        }
+        impl Translator for CountingTr {
+            /// Count glossary requests so we can check it is built once.
+            fn build_glossary(
+                &self,
+                _sample: &[String],
+                target_locales: &[&str],
+            ) -> Result<Glossary> {
+                assert_eq!(target_locales, ["es", "fr"]);
+                self.glossaries.fetch_add(1, Ordering::SeqCst);
+                Ok(Glossary {
+                    summary: "sum".into(),
+                    ..Glossary::default()
+                })
+            }
+
+            /// Translate by prefixing each line with the target locale.
+            fn translate_batch(
+                &self,
+                _summary: &str,
+                _prev: &[String],
+                lines: &[IndexedLine],
+                _source_language: &str,
+                target_locale: &str,
+            ) -> Result<Vec<IndexedLine>> {
+                Ok(lines
+                    .iter()
+                    .map(|l| IndexedLine {
+                        index: l.index,
+                        text: format!("{target_locale}:{}", l.text),
+                    })
+                    .collect())
+            }
+
+            fn model(&self) -> String {
+                "test".to_string()
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("orig.srt");
+        fs::write(&path, "1\n00:00:00,000 --> 00:00:01,000\nhello\n").unwrap();
+        let tr = Blocking::new(CountingTr {
+            glossaries: AtomicUsize::new(0),
+        });
+        let outs =
+            process_file_multi(&path, &tr, &["es", "fr"], &ProcessOptions::default()).unwrap();
+        assert_eq!(
+            outs,
+            vec![
+                dir.path().join("orig_es.srt"),
+                dir.path().join("orig_fr.srt")
+            ]
+        );
+        assert_eq!(tr.get_ref().glossaries.load(Ordering::SeqCst), 1);
+        assert!(fs::read_to_string(&outs[0]).unwrap().contains("es:hello"));
+        assert!(fs::read_to_string(&outs[1]).unwrap().contains("fr:hello"));
+    }
+
+    /// Ensure a season glossary is built from every episode, later runs
+    /// translate with it merged with their own glossary, and failed runs
+    /// leave the file unchanged.
+    #[test]
+    fn shares_series_glossary() {
+        /// Translator that turns every sample line into an upper-case term and
+        /// whose translation is the glossary it was given.
+        struct GlossaryTr;
+        impl Translator for GlossaryTr {
+            fn build_glossary(&self, sample: &[String], locales: &[&str]) -> Result<Glossary> {
+                Ok(Glossary {
+                    terms: sample
+                        .iter()
+                        .map(|l| Term {
+                            source: l.clone(),
+                            target: locales
+                                .iter()
+                                .map(|locale| (locale.to_string(), l.to_uppercase()))
+                                .collect(),
+                            ..Term::default()
+                        })
+                        .collect(),
+                    ..Glossary::default()
+                })
+            }
+
+            fn translate_batch(
+                &self,
+                summary: &str,
+                _prev: &[String],
+                lines: &[IndexedLine],
+                _source_language: &str,
+                _target_locale: &str,
+            ) -> Result<Vec<IndexedLine>> {
+                if lines.iter().any(|l| l.text == "Boom") {
+                    return Err(anyhow!("boom"));
+                }
+                Ok(lines
+                    .iter()
+                    .map(|l| IndexedLine {
+                        index: l.index,
+                        text: summary.replace('\n', "/"),
+                    })
+                    .collect())
+            }
+
+            fn model(&self) -> String {
+                "test".to_string()
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let episodes: Vec<PathBuf> = ["Kaito", "Ryu", "Mina", "Boom"]
+            .iter()
+            .enumerate()
+            .map(|(n, name)| {
+                let path = dir.path().join(format!("ep{}.srt", n + 1));
+                fs::write(&path, format!("1\n00:00:00,000 --> 00:00:01,000\n{name}\n")).unwrap();
+                path
+            })
+            .collect();
+        let glossary_path = dir.path().join("glossary.json");
+        let options = ProcessOptions {
+            glossary: Some(glossary_path.clone()),
+            ..ProcessOptions::default()
+        };
+
+        let glossary = build_series_glossary(
+            &episodes[..2],
+            &Blocking::new(GlossaryTr),
+            &["es"],
+            &options,
+        )
+        .unwrap();
+        let sources: Vec<&str> = glossary.terms.iter().map(|t| t.source.as_str()).collect();
+        assert_eq!(sources, ["Kaito", "Ryu"]);
+        assert_eq!(Glossary::load(&glossary_path).unwrap(), glossary);
+
+        let out = process_file(&episodes[2], &Blocking::new(GlossaryTr), "es", &options).unwrap();
+        assert!(fs::read_to_string(out)
+            .unwrap()
+            .contains("Terms:/- Kaito => KAITO/- Ryu => RYU/- Mina => MINA"));
+        assert_eq!(Glossary::load(&glossary_path).unwrap().terms.len(), 3);
+
+        assert!(process_file(&episodes[3], &Blocking::new(GlossaryTr), "es", &options).is_err());
+        assert_eq!(Glossary::load(&glossary_path).unwrap().terms.len(), 3);
+    }
+
+    /// Ensure a second run answers remembered lines from the translation memory
+    /// and only sends new ones.
+    #[test]
+    fn reuses_translation_memory() {
+        /// Translator that counts the lines it is asked for.
+        struct CountingTr {
+            lines: AtomicUsize,
+        }
+        impl Translator for CountingTr {
+            fn build_glossary(&self, _sample: &[String], _locales: &[&str]) -> Result<Glossary> {
+                Ok(Glossary::default())
             }
 
-            /// Translate by prefixing each line with `pt:` and keeping index.
             fn translate_batch(
                 &self,
                 _summary: &str,
                 _prev: &[String],
                 lines: &[IndexedLine],
+                _source_language: &str,
                 _target_locale: &str,
             ) -> Result<Vec<IndexedLine>> {
+                self.lines.fetch_add(lines.len(), Ordering::SeqCst);
                 Ok(lines
                     .iter()
                     .map(|l| IndexedLine {
@@ -307,19 +1818,63 @@ mod tests {
                     })
                     .collect())
             }
+
+            fn model(&self) -> String {
+                "test".to_string()
+            }
         }
 
         let dir = tempdir().unwrap();
-        let path = dir.path().join("orig.srt");
+        let options = ProcessOptions {
+            memory: Some(dir.path().join("memory.jsonl")),
+            ..ProcessOptions::default()
+        };
+        let tr = Blocking::new(CountingTr {
+            lines: AtomicUsize::new(0),
+        });
+        let first = dir.path().join("ep1.srt");
         fs::write(
-            &path,
-            "1\n00:00:00,000 --> 00:00:01,000\nhello\n\n2\n00:00:01,000 --> 00:00:02,000\nworld\n",
+            &first,
+            "1\n00:00:00,000 --> 00:00:01,000\nhello\n\n2\n00:00:01,000 --> 00:00:02,000\nThank you.\n",
         )
         .unwrap();
-        let out = process_file(&path, &MockTr, 50).unwrap();
-        assert_eq!(out, dir.path().join("orig_pt_br.srt"));
+        process_file(&first, &tr, "pt-BR", &options).unwrap();
+        assert_eq!(tr.get_ref().lines.load(Ordering::SeqCst), 2);
+
+        let second = dir.path().join("ep2.srt");
+        fs::write(
+            &second,
+            "1\n00:00:00,000 --> 00:00:01,000\nhello\n\n2\n00:00:01,000 --> 00:00:02,000\nThank you.\n\n3\n00:00:02,000 --> 00:00:03,000\nbye\n",
+        )
+        .unwrap();
+        let out = process_file(&second, &tr, "pt-BR", &options).unwrap();
+        assert_eq!(tr.get_ref().lines.load(Ordering::SeqCst), 3);
         let translated = fs::read_to_string(out).unwrap();
-        assert!(translated.contains("pt:hello"));
-        assert!(translated.contains("pt:world"));
+        assert!(translated.contains("pt:Thank you.\n"));
+        assert!(translated.contains("pt:bye\n"));
+
+        process_file(&second, &tr, "es", &options).unwrap();
+        assert_eq!(tr.get_ref().lines.load(Ordering::SeqCst), 6);
+    }
+
+    /// Ensure output names follow the target locale for SRT and video inputs.
+    #[test]
+    fn names_output_after_locale() {
+        assert_eq!(
+            output_path(Path::new("dir/orig.srt"), "srt", "es"),
+            PathBuf::from("dir/orig_es.srt")
+        );
+        assert_eq!(
+            output_path(Path::new("dir/orig.vtt"), "vtt", "fr"),
+            PathBuf::from("dir/orig_fr.vtt")
+        );
+        assert_eq!(
+            output_path(Path::new("dir/video.mkv"), "srt", "pt-BR"),
+            PathBuf::from("dir/video_pt_br.srt")
+        );
+        assert_eq!(
+            output_path(Path::new("dir/anime.mkv"), "ass", "es"),
+            PathBuf::from("dir/anime_es.ass")
+        );
     }
 }
@@ -0,0 +1,284 @@
+//! Local LLM translator for Ollama and llama.cpp servers.
+//! Nothing leaves the machine running the server, and output is constrained
+//! to the `translatedLines` JSON schema so small models stay on format.
+
+use super::{
+    block_on,
+    http::{self, RetryPolicy},
+    prompt, AsyncTranslator, Glossary, IndexedLine, Translator,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::{debug, trace};
+
+/// Model used when `OLLAMA_MODEL` is not set.
+const DEFAULT_MODEL: &str = "qwen2.5:7b";
+
+/// Which HTTP API the local server speaks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ApiStyle {
+    /// Ollama's native `/api/chat` endpoint.
+    Ollama,
+    /// The OpenAI-compatible `/v1/chat/completions` endpoint of llama.cpp and Ollama.
+    OpenAi,
+}
+
+/// Translator that delegates to a local Ollama or llama.cpp server.
+pub struct OllamaTranslator {
+    client: Client,
+    retry: RetryPolicy,
+    base_url: String,
+    model: String,
+    api: ApiStyle,
+}
+
+impl OllamaTranslator {
+    /// Create a new translator from `OLLAMA_*` environment variables.
+    /// `OLLAMA_BASE_URL` (default `http://localhost:11434`), `OLLAMA_MODEL` and
+    /// `OLLAMA_TIMEOUT_SECS` (default 600) pick the server, model and timeout;
+    /// `OLLAMA_API=openai` switches to the OpenAI-compatible endpoint llama.cpp serves.
+    pub fn new() -> Result<Self> {
+        trace!("OllamaTranslator::new");
+        let base = std::env::var("OLLAMA_BASE_URL")
+            .unwrap_or_else(|_| "http://localhost:11434".to_string());
+        let model = std::env::var("OLLAMA_MODEL").unwrap_or_else(|_| DEFAULT_MODEL.to_string());
+        let api = match std::env::var("OLLAMA_API").as_deref() {
+            Err(_) | Ok("ollama") => ApiStyle::Ollama,
+            Ok("openai") => ApiStyle::OpenAi,
+            Ok(other) => {
+                return Err(anyhow!(
+                    "unknown OLLAMA_API {other}; expected ollama or openai"
+                ))
+            }
+        };
+        let timeout = std::env::var("OLLAMA_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(600);
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .timeout(Duration::from_secs(timeout))
+            .build()?;
+        debug!("using base_url={base} model={model} api={api:?}");
+        Ok(Self {
+            client,
+            retry: RetryPolicy::default(),
+            base_url: base,
+            model,
+            api,
+        })
+    }
+
+    /// Use `retry` instead of the default retry policy.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Send `system` and `user` messages and return the reply text.
+    /// When `schema` is given the server must answer with JSON matching it.
+    async fn chat(&self, system: &str, user: &str, schema: Option<Value>) -> Result<String> {
+        trace!("chat");
+        let messages = json!([
+            { "role": "system", "content": system },
+            { "role": "user", "content": user },
+        ]);
+        let (path, mut body) = match self.api {
+            ApiStyle::Ollama => (
+                "/api/chat",
+                json!({
+                    "model": self.model,
+                    "messages": messages,
+                    "stream": false,
+                    "options": {"temperature": 0},
+                }),
+            ),
+            ApiStyle::OpenAi => (
+                "/v1/chat/completions",
+                json!({
+                    "model": self.model,
+                    "messages": messages,
+                    "temperature": 0,
+                }),
+            ),
+        };
+        if let Some(schema) = schema {
+            match self.api {
+                ApiStyle::Ollama => body["format"] = schema,
+                ApiStyle::OpenAi => {
+                    body["response_format"] = json!({
+                        "type": "json_schema",
+                        "json_schema": {"name": "translation", "strict": true, "schema": schema},
+                    })
+                }
+            }
+        }
+        debug!(request = %body);
+        let url = format!("{}{}", self.base_url, path);
+        let value =
+            http::send_json("Ollama", &self.retry, || self.client.post(&url).json(&body)).await?;
+        let content = match self.api {
+            ApiStyle::Ollama => &value["message"]["content"],
+            ApiStyle::OpenAi => &value["choices"][0]["message"]["content"],
+        };
+        content
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("missing content"))
+    }
+}
+
+#[async_trait]
+impl AsyncTranslator for OllamaTranslator {
+    /// Translate a batch of subtitle lines, using summary and previous context.
+    async fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        trace!("translate_batch lines={} prev={}", lines.len(), prev.len());
+        let (system_prompt, user_prompt) =
+            prompt::translation(summary, prev, lines, source_language, target_locale)?;
+        let content = self
+            .chat(
+                &system_prompt,
+                &user_prompt,
+                Some(prompt::translation_schema()),
+            )
+            .await?;
+        prompt::parse_translated_lines(&content)
+    }
+
+    /// Ask the local model for a summary and glossary based on sample lines.
+    async fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        trace!("build_glossary sample_lines={}", sample.len());
+        let system_prompt = prompt::glossary(target_locales)?;
+        let content = self
+            .chat(
+                &system_prompt,
+                &sample.join("\n"),
+                Some(prompt::glossary_schema(target_locales)?),
+            )
+            .await?;
+        prompt::parse_glossary(&content, target_locales)
+    }
+
+    /// Name used to key translation memory entries.
+    fn model(&self) -> String {
+        format!("ollama/{}", self.model)
+    }
+}
+
+impl Translator for OllamaTranslator {
+    /// Blocking version of `AsyncTranslator::translate_batch`.
+    fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        block_on(AsyncTranslator::translate_batch(
+            self,
+            summary,
+            prev,
+            lines,
+            source_language,
+            target_locale,
+        ))?
+    }
+
+    /// Blocking version of `AsyncTranslator::build_glossary`.
+    fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        block_on(AsyncTranslator::build_glossary(
+            self,
+            sample,
+            target_locales,
+        ))?
+    }
+
+    fn model(&self) -> String {
+        AsyncTranslator::model(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::MockServer;
+    use tokio::sync::Mutex;
+
+    static ENV_LOCK: Mutex<()> = Mutex::const_new(());
+
+    fn line() -> IndexedLine {
+        IndexedLine {
+            index: 1,
+            text: "hi".into(),
+        }
+    }
+
+    /// Verify the native API gets the schema as `format` and its answer is parsed.
+    #[tokio::test]
+    async fn translates_with_ollama_api() {
+        let _guard = ENV_LOCK.lock().await;
+        let server = MockServer::start_async().await;
+        std::env::set_var("OLLAMA_BASE_URL", server.base_url());
+        std::env::remove_var("OLLAMA_API");
+        let m = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/api/chat")
+                    .body_contains("\"format\":{")
+                    .body_contains("\"stream\":false");
+                let content = serde_json::to_string(&json!({
+                    "translatedLines": [{"index": "1", "translation": "hallo"}]
+                }))
+                .unwrap();
+                then.status(200)
+                    .json_body(json!({"message": {"role": "assistant", "content": content}}));
+            })
+            .await;
+        let tr = OllamaTranslator::new().unwrap();
+        let out = AsyncTranslator::translate_batch(&tr, "sum", &[], &[line()], "en", "de")
+            .await
+            .unwrap();
+        assert_eq!(out[0].text, "hallo");
+        m.assert_async().await;
+    }
+
+    /// Verify the OpenAI-compatible API gets a strict `json_schema` response format.
+    #[tokio::test]
+    async fn translates_with_openai_compatible_api() {
+        let _guard = ENV_LOCK.lock().await;
+        let server = MockServer::start_async().await;
+        std::env::set_var("OLLAMA_BASE_URL", server.base_url());
+        std::env::set_var("OLLAMA_API", "openai");
+        let m = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/v1/chat/completions")
+                    .body_contains("\"type\":\"json_schema\"");
+                let content = serde_json::to_string(&json!({
+                    "translatedLines": [{"index": "1", "translation": "ciao"}]
+                }))
+                .unwrap();
+                then.status(200)
+                    .json_body(json!({"choices": [{"message": {"content": content}}]}));
+            })
+            .await;
+        let tr = OllamaTranslator::new().unwrap();
+        let out = AsyncTranslator::translate_batch(&tr, "sum", &[], &[line()], "en", "it")
+            .await
+            .unwrap();
+        std::env::remove_var("OLLAMA_API");
+        assert_eq!(out[0].text, "ciao");
+        m.assert_async().await;
+    }
+}
@@ -1,33 +1,100 @@
 //! OpenAI-backed translator implementation.
-//! This uses the GPT-5 nano model with JSON mode for subtitle translation.
+//! This uses GPT-5 nano with strict JSON-schema structured output by default;
+//! the model and sampling parameters are configurable through `OpenAiConfig`.
 
-use super::{IndexedLine, Translator};
+use super::{
+    block_on,
+    http::{self, RetryPolicy},
+    prompt, AsyncTranslator, Glossary, IndexedLine, TranslationError, Translator,
+};
 use anyhow::{anyhow, Result};
-use reqwest::blocking::Client;
-use serde_json::{json, Value};
-use std::time::{Duration, Instant};
-use tracing::{debug, info, trace};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+use tracing::{debug, trace};
 
-/// Default human-readable language name used in prompts.
-const DEFAULT_LANGUAGE: &str = "Brazilian Portuguese";
+/// Model used for translation when none is configured.
+pub const DEFAULT_MODEL: &str = "gpt-5-nano";
 
-/// Replace the `$LANGUAGE` token in the provided template with `language`.
-fn with_language(template: &str, language: &str) -> String {
-    // Here we swap the language placeholder so prompts can be edited independently from code.
-    template.replace("$LANGUAGE", language)
+/// Model and sampling parameters for OpenAI requests.
+/// Unset options are left out of the request so the API defaults apply.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenAiConfig {
+    /// Model translating the subtitle batches.
+    pub model: String,
+    /// Model building the glossary; `model` when unset.
+    pub glossary_model: Option<String>,
+    pub temperature: Option<f32>,
+    /// Sent as `max_completion_tokens`.
+    pub max_output_tokens: Option<u32>,
+    /// `minimal`, `low`, `medium` or `high` for reasoning models.
+    pub reasoning_effort: Option<String>,
+    pub seed: Option<u64>,
+    /// Extra fields merged into every request body, overriding the ones above.
+    pub extra_body: Map<String, Value>,
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            model: DEFAULT_MODEL.to_string(),
+            glossary_model: None,
+            temperature: None,
+            max_output_tokens: None,
+            reasoning_effort: None,
+            seed: None,
+            extra_body: Map::new(),
+        }
+    }
+}
+
+impl OpenAiConfig {
+    /// Build a chat completion body for `model` with the configured parameters.
+    fn body(&self, model: &str, messages: Vec<Value>) -> Value {
+        let mut body = json!({
+            "model": model,
+            "messages": messages,
+        });
+        if let Some(temperature) = self.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(max) = self.max_output_tokens {
+            body["max_completion_tokens"] = json!(max);
+        }
+        if let Some(effort) = &self.reasoning_effort {
+            body["reasoning_effort"] = json!(effort);
+        }
+        if let Some(seed) = self.seed {
+            body["seed"] = json!(seed);
+        }
+        for (key, value) in &self.extra_body {
+            body[key] = value.clone();
+        }
+        body
+    }
 }
 
 /// Translator that delegates to the OpenAI chat completion API.
 pub struct OpenAiTranslator {
     client: Client,
+    retry: RetryPolicy,
     api_key: String,
     base_url: String,
+    config: OpenAiConfig,
 }
 
 impl OpenAiTranslator {
     /// Create a new translator reading the API key from `OPENAI_API_KEY`.
     pub fn new() -> Result<Self> {
-        trace!("OpenAiTranslator::new");
+        Self::with_config(OpenAiConfig::default())
+    }
+
+    /// Create a new translator using `config` for every request.
+    pub fn with_config(config: OpenAiConfig) -> Result<Self> {
+        trace!("OpenAiTranslator::with_config model={}", config.model);
         let key = std::env::var("OPENAI_API_KEY")?;
         let base = std::env::var("OPENAI_BASE_URL")
             .unwrap_or_else(|_| "https://api.openai.com".to_string());
@@ -42,73 +109,207 @@ impl OpenAiTranslator {
         debug!("using base_url={base}");
         Ok(Self {
             client,
+            retry: RetryPolicy::default(),
             api_key: key,
             base_url: base,
+            config,
         })
     }
 
+    /// Use `retry` instead of the default retry policy.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
     /// Send a JSON body to the chat completions endpoint and return the JSON response.
-    fn post_chat(&self, body: Value) -> Result<Value> {
+    async fn post_chat(&self, body: Value) -> Result<Value> {
         trace!("post_chat");
         debug!(request = %body);
         let url = format!("{}/v1/chat/completions", self.base_url);
-        loop {
-            info!("sending request to OpenAI");
-            let start = Instant::now();
-            let resp = self
-                .client
+        http::send_json("OpenAI", &self.retry, || {
+            self.client
                 .post(&url)
                 .bearer_auth(&self.api_key)
                 .json(&body)
-                .send();
-            let resp = match resp {
-                Ok(r) => r,
-                Err(err) => {
-                    if err.is_timeout() {
-                        info!(
-                            "openai request timed out after {} ms, retrying",
-                            start.elapsed().as_millis()
-                        );
-                        debug!(?err);
-                        continue;
-                    }
-                    info!(
-                        "openai request failed after {} ms",
-                        start.elapsed().as_millis()
-                    );
-                    debug!(?err);
-                    return Err(err.into());
-                }
-            };
-            let status = resp.status();
-            let text = resp.text()?;
-            info!(
-                "openai responded in {} ms with status {}",
-                start.elapsed().as_millis(),
-                status
-            );
-            debug!(response = %text);
-            if !status.is_success() {
-                return Err(anyhow!("openai error: {status} {text}"));
-            }
-            return Ok(serde_json::from_str(&text)?);
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl AsyncTranslator for OpenAiTranslator {
+    /// Translate a batch of subtitle lines, using summary and previous context.
+    async fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        trace!("translate_batch lines={} prev={}", lines.len(), prev.len());
+        let (system_prompt, user_prompt) =
+            prompt::translation(summary, prev, lines, source_language, target_locale)?;
+        let messages = vec![
+            json!({ "role": "system", "content": system_prompt }),
+            json!({ "role": "user", "content": user_prompt }),
+        ];
+        let mut body = self.config.body(&self.config.model, messages);
+        if body.get("response_format").is_none() {
+            body["response_format"] = json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "translated_lines",
+                    "strict": true,
+                    "schema": prompt::translation_schema(),
+                },
+            });
+        }
+        let value = self.post_chat(body).await?;
+        let choice = &value["choices"][0];
+        if let Some(refusal) = choice["message"]["refusal"].as_str() {
+            return Err(TranslationError::Refusal(refusal.to_string()).into());
+        }
+        if choice["finish_reason"] == "length" {
+            return Err(TranslationError::Truncated.into());
+        }
+        let content = choice["message"]["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("missing content"))?;
+        prompt::parse_translated_lines(content)
+    }
+    /// Ask OpenAI for a summary and glossary based on sample lines.
+    async fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        trace!("build_glossary sample_lines={}", sample.len());
+        let text = sample.join("\n");
+        let system_prompt = prompt::glossary(target_locales)?;
+        let messages = vec![
+            json!({ "role": "system", "content": system_prompt }),
+            json!({ "role": "user", "content": text }),
+        ];
+        let model = self
+            .config
+            .glossary_model
+            .as_deref()
+            .unwrap_or(&self.config.model);
+        let mut body = self.config.body(model, messages);
+        if body.get("response_format").is_none() {
+            body["response_format"] = json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "glossary",
+                    "strict": true,
+                    "schema": prompt::glossary_schema(target_locales)?,
+                },
+            });
         }
+        let value = self.post_chat(body).await?;
+        let content = value["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("missing content"))?;
+        prompt::parse_glossary(content, target_locales)
+    }
+
+    /// Name used to key translation memory entries.
+    fn model(&self) -> String {
+        format!("openai/{}", self.config.model)
+    }
+}
+
+impl Translator for OpenAiTranslator {
+    /// Blocking version of `AsyncTranslator::translate_batch`.
+    fn translate_batch(
+        &self,
+        summary: &str,
+        prev: &[String],
+        lines: &[IndexedLine],
+        source_language: &str,
+        target_locale: &str,
+    ) -> Result<Vec<IndexedLine>> {
+        block_on(AsyncTranslator::translate_batch(
+            self,
+            summary,
+            prev,
+            lines,
+            source_language,
+            target_locale,
+        ))?
+    }
+
+    /// Blocking version of `AsyncTranslator::build_glossary`.
+    fn build_glossary(&self, sample: &[String], target_locales: &[&str]) -> Result<Glossary> {
+        block_on(AsyncTranslator::build_glossary(
+            self,
+            sample,
+            target_locales,
+        ))?
+    }
+
+    fn model(&self) -> String {
+        AsyncTranslator::model(self)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::translate::Blocking;
     use httpmock::MockServer;
     use serde_json::json;
-    use std::sync::Mutex;
+    use tokio::sync::Mutex;
 
-    static ENV_LOCK: Mutex<()> = Mutex::new(());
+    static ENV_LOCK: Mutex<()> = Mutex::const_new(());
 
     /// Verify that we can translate a batch using a mocked OpenAI server.
+    #[tokio::test]
+    async fn translates_with_mock_server() {
+        let _guard = ENV_LOCK.lock().await;
+        std::env::set_var("OPENAI_API_KEY", "test");
+        let server = MockServer::start_async().await;
+        std::env::set_var("OPENAI_BASE_URL", server.base_url());
+        let _m = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/v1/chat/completions");
+                let content = serde_json::to_string(&json!({
+                    "translatedLines": [{"index": "1", "translation": "ola"}]
+                }))
+                .unwrap();
+                then.status(200).json_body(json!({
+                    "choices": [{
+                        "message": {"content": content}
+                    }]
+                }));
+            })
+            .await;
+        let tr = OpenAiTranslator::new().unwrap();
+        let out = AsyncTranslator::translate_batch(
+            &tr,
+            "sum",
+            &[],
+            &[IndexedLine {
+                index: 1,
+                text: "hi".into(),
+            }],
+            "en",
+            "pt-BR",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            out,
+            vec![IndexedLine {
+                index: 1,
+                text: "ola".to_string()
+            }]
+        );
+    }
+
+    /// Verify the blocking `Translator` calls work outside of any runtime.
     #[test]
-    fn translates_with_mock_server() {
-        let _guard = ENV_LOCK.lock().unwrap();
+    fn translates_without_runtime() {
+        let _guard = ENV_LOCK.blocking_lock();
         std::env::set_var("OPENAI_API_KEY", "test");
         let server = MockServer::start();
         std::env::set_var("OPENAI_BASE_URL", server.base_url());
@@ -126,53 +327,220 @@ mod tests {
             }));
         });
         let tr = OpenAiTranslator::new().unwrap();
-        let out = tr
-            .translate_batch(
-                "sum",
-                &[],
-                &[IndexedLine {
-                    index: 1,
-                    text: "hi".into(),
-                }],
-                "pt-BR",
-            )
+        let lines = [IndexedLine {
+            index: 1,
+            text: "hi".into(),
+        }];
+        let out = Translator::translate_batch(&tr, "sum", &[], &lines, "en", "pt-BR").unwrap();
+        assert_eq!(out[0].text, "ola");
+        // A second call reuses the client and its pooled connection.
+        let out = Translator::translate_batch(&tr, "sum", &[], &lines, "en", "pt-BR").unwrap();
+        assert_eq!(out[0].text, "ola");
+    }
+
+    /// Verify the backend's blocking `Translator` works from async code when
+    /// wrapped in `Blocking`.
+    #[tokio::test]
+    async fn translates_wrapped_in_blocking() {
+        let _guard = ENV_LOCK.lock().await;
+        std::env::set_var("OPENAI_API_KEY", "test");
+        let server = MockServer::start_async().await;
+        std::env::set_var("OPENAI_BASE_URL", server.base_url());
+        let m = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/v1/chat/completions");
+                let content = serde_json::to_string(&json!({
+                    "translatedLines": [{"index": "1", "translation": "ola"}]
+                }))
+                .unwrap();
+                then.status(200).json_body(json!({
+                    "choices": [{
+                        "message": {"content": content}
+                    }]
+                }));
+            })
+            .await;
+        let tr = Blocking::new(OpenAiTranslator::new().unwrap());
+        let lines = [IndexedLine {
+            index: 1,
+            text: "hi".into(),
+        }];
+        for _ in 0..2 {
+            let out = tr
+                .translate_batch("sum", &[], &lines, "en", "pt-BR")
+                .await
+                .unwrap();
+            assert_eq!(out[0].text, "ola");
+        }
+        m.assert_hits_async(2).await;
+    }
+
+    /// Verify the glossary prompt mentions Brazilian Portuguese.
+    #[tokio::test]
+    async fn glossary_mentions_language() {
+        let _guard = ENV_LOCK.lock().await;
+        std::env::set_var("OPENAI_API_KEY", "test");
+        let server = MockServer::start_async().await;
+        std::env::set_var("OPENAI_BASE_URL", server.base_url());
+        let m = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/v1/chat/completions")
+                    .body_contains("Brazilian Portuguese")
+                    .body_contains("\"name\":\"glossary\"")
+                    .body_contains("\"required\":[\"pt-BR\"]");
+                let content = serde_json::to_string(&json!({
+                    "summary": "sum",
+                    "terms": [{"source": "Kaito", "target": {"pt-BR": "Kaito"}, "note": null, "doNotTranslate": true}],
+                    "characters": [],
+                }))
+                .unwrap();
+                then.status(200).json_body(json!({
+                    "choices": [{
+                        "message": {"content": content}
+                    }]
+                }));
+            })
+            .await;
+        let tr = OpenAiTranslator::new().unwrap();
+        let out = AsyncTranslator::build_glossary(&tr, &["hi".to_string()], &["pt-BR"])
+            .await
             .unwrap();
-        assert_eq!(
-            out,
-            vec![IndexedLine {
+        assert_eq!(out.summary, "sum");
+        assert!(out.terms[0].do_not_translate);
+        assert_eq!(out.terms[0].target["pt-BR"], "Kaito");
+        m.assert_async().await;
+    }
+
+    /// Verify the translation prompt names the source and target languages and
+    /// uses the examples of the target.
+    #[tokio::test]
+    async fn translation_prompt_uses_target_locale() {
+        let _guard = ENV_LOCK.lock().await;
+        std::env::set_var("OPENAI_API_KEY", "test");
+        let server = MockServer::start_async().await;
+        std::env::set_var("OPENAI_BASE_URL", server.base_url());
+        let m = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/v1/chat/completions")
+                    .body_contains("Japanese subtitles to Spanish")
+                    .body_contains("field with Japanese text")
+                    .body_contains("Hay una masa asesina");
+                let content = serde_json::to_string(&json!({
+                    "translatedLines": [{"index": "1", "translation": "hola"}]
+                }))
+                .unwrap();
+                then.status(200).json_body(json!({
+                    "choices": [{
+                        "message": {"content": content}
+                    }]
+                }));
+            })
+            .await;
+        let tr = OpenAiTranslator::new().unwrap();
+        let out = AsyncTranslator::translate_batch(
+            &tr,
+            "sum",
+            &[],
+            &[IndexedLine {
                 index: 1,
-                text: "ola".to_string()
-            }]
-        );
+                text: "hi".into(),
+            }],
+            "jpn",
+            "es",
+        )
+        .await
+        .unwrap();
+        assert_eq!(out[0].text, "hola");
+        m.assert_async().await;
     }
 
-    /// Verify the glossary prompt mentions Brazilian Portuguese.
-    #[test]
-    fn glossary_mentions_language() {
-        let _guard = ENV_LOCK.lock().unwrap();
+    /// Verify the strict schema is requested and refusals surface as typed errors.
+    #[tokio::test]
+    async fn requests_schema_and_reports_refusal() {
+        let _guard = ENV_LOCK.lock().await;
         std::env::set_var("OPENAI_API_KEY", "test");
-        let server = MockServer::start();
+        let server = MockServer::start_async().await;
         std::env::set_var("OPENAI_BASE_URL", server.base_url());
-        let m = server.mock(|when, then| {
-            when.method(httpmock::Method::POST)
-                .path("/v1/chat/completions")
-                .body_contains("Brazilian Portuguese");
-            then.status(200).json_body(json!({
-                "choices": [{
-                    "message": {"content": "sum"}
-                }]
-            }));
-        });
+        let m = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/v1/chat/completions")
+                    .body_contains("\"type\":\"json_schema\"")
+                    .body_contains("\"strict\":true");
+                then.status(200).json_body(json!({
+                    "choices": [{
+                        "finish_reason": "stop",
+                        "message": {"content": null, "refusal": "I can't help with that."}
+                    }]
+                }));
+            })
+            .await;
         let tr = OpenAiTranslator::new().unwrap();
-        let out = tr.build_glossary(&["hi".to_string()]).unwrap();
-        assert_eq!(out, "sum");
-        m.assert();
+        let err = AsyncTranslator::translate_batch(
+            &tr,
+            "sum",
+            &[],
+            &[IndexedLine {
+                index: 1,
+                text: "hi".into(),
+            }],
+            "en",
+            "pt-BR",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.downcast::<TranslationError>().unwrap(),
+            TranslationError::Refusal("I can't help with that.".into())
+        );
+        m.assert_async().await;
+    }
+
+    /// Verify the configured models and parameters reach the request body.
+    #[tokio::test]
+    async fn sends_configured_parameters() {
+        let _guard = ENV_LOCK.lock().await;
+        std::env::set_var("OPENAI_API_KEY", "test");
+        let server = MockServer::start_async().await;
+        std::env::set_var("OPENAI_BASE_URL", server.base_url());
+        let m = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::POST)
+                    .path("/v1/chat/completions")
+                    .body_contains("\"model\":\"gpt-5\"")
+                    .body_contains("\"seed\":7")
+                    .body_contains("\"reasoning_effort\":\"high\"")
+                    .body_contains("\"store\":false");
+                then.status(200).json_body(json!({
+                    "choices": [{"message": {"content": "{\"summary\": \"sum\"}"}}]
+                }));
+            })
+            .await;
+        let config = OpenAiConfig {
+            glossary_model: Some("gpt-5".into()),
+            reasoning_effort: Some("high".into()),
+            seed: Some(7),
+            extra_body: json!({"store": false}).as_object().unwrap().clone(),
+            ..OpenAiConfig::default()
+        };
+        let tr = OpenAiTranslator::with_config(config).unwrap();
+        assert_eq!(
+            AsyncTranslator::build_glossary(&tr, &["hi".into()], &["fr"])
+                .await
+                .unwrap()
+                .summary,
+            "sum"
+        );
+        m.assert_async().await;
     }
 
     /// Ensure we retry when the first request times out.
-    #[test]
-    fn retries_on_timeout() {
-        let _guard = ENV_LOCK.lock().unwrap();
+    #[tokio::test]
+    async fn retries_on_timeout() {
+        let _guard = ENV_LOCK.lock().await;
         std::env::set_var("OPENAI_API_KEY", "test");
         std::env::set_var("OPENAI_TIMEOUT_SECS", "1");
         use std::io::{Read, Write};
@@ -208,17 +576,19 @@ mod tests {
         });
         std::env::set_var("OPENAI_BASE_URL", format!("http://{}", addr));
         let tr = OpenAiTranslator::new().unwrap();
-        let out = tr
-            .translate_batch(
-                "sum",
-                &[],
-                &[IndexedLine {
-                    index: 1,
-                    text: "hi".into(),
-                }],
-                "pt-BR",
-            )
-            .unwrap();
+        let out = AsyncTranslator::translate_batch(
+            &tr,
+            "sum",
+            &[],
+            &[IndexedLine {
+                index: 1,
+                text: "hi".into(),
+            }],
+            "en",
+            "pt-BR",
+        )
+        .await
+        .unwrap();
         assert_eq!(
             out,
             vec![IndexedLine {
@@ -229,111 +599,3 @@ mod tests {
         std::env::remove_var("OPENAI_TIMEOUT_SECS");
     }
 }
-
-impl Translator for OpenAiTranslator {
-    /// Translate a batch of subtitle lines, using summary and previous context.
-    fn translate_batch(
-        &self,
-        summary: &str,
-        prev: &[String],
-        lines: &[IndexedLine],
-        target_locale: &str,
-    ) -> Result<Vec<IndexedLine>> {
-        trace!("translate_batch lines={} prev={}", lines.len(), prev.len());
-        let prev_text = prev.join("\n");
-        let curr_json = json!({
-            "translatedLines": lines
-                .iter()
-                .map(|l| json!({
-                    "index": l.index.to_string(),
-                    "translation": l.text.clone(),
-                }))
-                .collect::<Vec<_>>()
-        });
-        let curr_text = serde_json::to_string_pretty(&curr_json)?;
-        let example_in = r#"{
-  "translatedLines" :[
-    {
-      "index": "1",
-      "translation": "<i>- Previously on</i> \n<i>\"President Alien\"...</i>"
-    },{
-      "index": "2",
-      "translation": "<i>- There is a deadly blob</i>\n<i>running around.</i>"
-    },{
-      "index": "3",
-      "translation": "- I called in\nAgent Baxter Boy"
-    }]
-}"#;
-        let example_out = r#"{
-  "translatedLines" :[
-    {
-      "index": "1",
-      "translation": "<i>-Anteriormente em</i> \n<i>\"Presidente Alien\"...</i>"
-    },{
-      "index": "2",
-      "translation": "<i>- Tem um blob assassino</i>\n<i>à solta.</i>"
-    },{
-      "index": "3",
-      "translation": "- Eu chamei o \nAgente Baxter Boy"
-    }]
-}"#;
-        let system_prompt = with_language(
-            include_str!("prompts/translate_system.prompt"),
-            DEFAULT_LANGUAGE,
-        );
-        let user_prompt = include_str!("prompts/translate_user.prompt")
-            .replace("$SUMMARY", summary)
-            .replace("$PREVIOUS_LINES", &prev_text)
-            .replace("$TARGET_LOCALE", target_locale)
-            .replace("$EXAMPLE_IN", example_in)
-            .replace("$EXAMPLE_OUT", example_out)
-            .replace("$LINES", &curr_text);
-        let messages = vec![
-            json!({ "role": "system", "content": system_prompt }),
-            json!({ "role": "user", "content": user_prompt }),
-        ];
-        let body = json!({
-            "model": "gpt-5-nano",
-            "response_format": {"type": "json_object"},
-            "messages": messages,
-        });
-        let value = self.post_chat(body)?;
-        let content = value["choices"][0]["message"]["content"]
-            .as_str()
-            .ok_or_else(|| anyhow!("missing content"))?;
-        let data: Value = serde_json::from_str(content)?;
-        let arr = data["translatedLines"]
-            .as_array()
-            .ok_or_else(|| anyhow!("no translatedLines"))?;
-        Ok(arr
-            .iter()
-            .filter_map(|v| {
-                let idx = v["index"].as_str()?.parse().ok()?;
-                let text = v["translation"].as_str()?.to_string();
-                Some(IndexedLine { index: idx, text })
-            })
-            .collect())
-    }
-    /// Ask OpenAI for a summary and glossary based on sample lines.
-    fn build_glossary(&self, sample: &[String]) -> Result<String> {
-        trace!("build_glossary sample_lines={}", sample.len());
-        let text = sample.join("\n");
-        let system_prompt = with_language(
-            include_str!("prompts/glossary_system.prompt"),
-            DEFAULT_LANGUAGE,
-        );
-        let messages = vec![
-            json!({ "role": "system", "content": system_prompt }),
-            json!({ "role": "user", "content": text }),
-        ];
-        let body = json!({
-            "model": "gpt-5-nano",
-            "messages": messages,
-        });
-        let value = self.post_chat(body)?;
-        let content = value["choices"][0]["message"]["content"]
-            .as_str()
-            .ok_or_else(|| anyhow!("missing content"))?;
-        Ok(content.to_string())
-    }
-}
@@ -0,0 +1,388 @@
+//! Prompt building and response parsing shared by the LLM backends.
+//! Prompts live in `prompts/` so they can be edited independently from code.
+
+use super::{Character, Glossary, IndexedLine, Term, TranslationError};
+use crate::locale::{self, Locale};
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// Replace the `$LANGUAGE` token in the provided template with `language`.
+pub(crate) fn with_language(template: &str, language: &str) -> String {
+    // Here we swap the language placeholder so prompts can be edited independently from code.
+    template.replace("$LANGUAGE", language)
+}
+
+/// Pick the few-shot example output written in the language of `locale`.
+/// Examples are keyed by the primary language subtag so regional variants share them.
+pub(crate) fn example_output(locale: &Locale) -> &'static str {
+    match locale::primary_subtag(locale.tag) {
+        "es" => include_str!("prompts/examples/es.json"),
+        "fr" => include_str!("prompts/examples/fr.json"),
+        "de" => include_str!("prompts/examples/de.json"),
+        "it" => include_str!("prompts/examples/it.json"),
+        "ja" => include_str!("prompts/examples/ja.json"),
+        "ko" => include_str!("prompts/examples/ko.json"),
+        "zh" => include_str!("prompts/examples/zh.json"),
+        "en" => include_str!("prompts/examples/en.json"),
+        _ => include_str!("prompts/examples/pt.json"),
+    }
+}
+
+/// Build the system and user prompts asking to translate `lines` from
+/// `source_language`, any alias `locale::resolve_language` accepts, to
+/// `target_locale`.
+pub(crate) fn translation(
+    summary: &str,
+    prev: &[String],
+    lines: &[IndexedLine],
+    source_language: &str,
+    target_locale: &str,
+) -> Result<(String, String)> {
+    let curr_json = json!({
+        "translatedLines": lines
+            .iter()
+            .map(|l| json!({
+                "index": l.index.to_string(),
+                "translation": l.text.clone(),
+            }))
+            .collect::<Vec<_>>()
+    });
+    let curr_text = serde_json::to_string_pretty(&curr_json)?;
+    let locale = locale::resolve(target_locale)?;
+    let source = locale::resolve_language(source_language)?.name;
+    let system_prompt = with_language(include_str!("prompts/translate_system.prompt"), locale.name)
+        .replace("$SOURCE_LANGUAGE", source);
+    let user_prompt = include_str!("prompts/translate_user.prompt")
+        .replace("$SOURCE_LANGUAGE", source)
+        .replace("$SUMMARY", summary)
+        .replace("$PREVIOUS_LINES", &prev.join("\n"))
+        .replace("$TARGET_LOCALE", target_locale)
+        .replace("$EXAMPLE_IN", include_str!("prompts/examples/en.json"))
+        .replace("$EXAMPLE_OUT", example_output(locale))
+        .replace("$LINES", &curr_text);
+    Ok((system_prompt, user_prompt))
+}
+
+/// Build the system prompt asking for a summary and glossary for `target_locales`.
+pub(crate) fn glossary(target_locales: &[&str]) -> Result<String> {
+    Ok(with_language(
+        include_str!("prompts/glossary_system.prompt"),
+        &locale::describe(target_locales)?,
+    )
+    .replace("$LOCALES", &locale_tags(target_locales)?.join(", ")))
+}
+
+/// Resolve `target_locales` to the tags glossary translations are keyed by.
+fn locale_tags(target_locales: &[&str]) -> Result<Vec<&'static str>> {
+    target_locales
+        .iter()
+        .map(|tag| locale::resolve(tag).map(|l| l.tag))
+        .collect()
+}
+
+/// JSON schema of the `translatedLines` answer, for backends that constrain output.
+pub(crate) fn translation_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "translatedLines": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "index": {"type": "string"},
+                        "translation": {"type": "string"},
+                    },
+                    "required": ["index", "translation"],
+                    "additionalProperties": false,
+                },
+            },
+        },
+        "required": ["translatedLines"],
+        "additionalProperties": false,
+    })
+}
+
+/// JSON schema of the glossary answer, for backends that constrain output.
+/// Optional fields are nullable because strict schemas require every property,
+/// and `target` has one property per locale in `target_locales`.
+pub(crate) fn glossary_schema(target_locales: &[&str]) -> Result<Value> {
+    let text = json!({"type": "string"});
+    let nullable = json!({"type": ["string", "null"]});
+    let tags = locale_tags(target_locales)?;
+    let targets: Map<String, Value> = tags
+        .iter()
+        .map(|tag| (tag.to_string(), text.clone()))
+        .collect();
+    Ok(json!({
+        "type": "object",
+        "properties": {
+            "summary": text,
+            "terms": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "source": text,
+                        "target": {
+                            "type": "object",
+                            "properties": targets,
+                            "required": tags,
+                            "additionalProperties": false,
+                        },
+                        "note": nullable,
+                        "doNotTranslate": {"type": "boolean"},
+                    },
+                    "required": ["source", "target", "note", "doNotTranslate"],
+                    "additionalProperties": false,
+                },
+            },
+            "characters": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": text,
+                        "gender": nullable,
+                        "formality": nullable,
+                    },
+                    "required": ["name", "gender", "formality"],
+                    "additionalProperties": false,
+                },
+            },
+        },
+        "required": ["summary", "terms", "characters"],
+        "additionalProperties": false,
+    }))
+}
+
+/// Parse the glossary JSON a model returned for `target_locales`.
+/// Text around the outermost braces is ignored like in `parse_translated_lines`.
+/// Entries without a source term or name are rejected; missing optional
+/// fields and empty strings are treated as unknown. A `target` given as a
+/// single string instead of an object applies to every locale.
+pub(crate) fn parse_glossary(content: &str, target_locales: &[&str]) -> Result<Glossary> {
+    let tags = locale_tags(target_locales)?;
+    let json = match (content.find('{'), content.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => return Err(TranslationError::InvalidJson("no JSON object".into()).into()),
+    };
+    let data: Value =
+        serde_json::from_str(json).map_err(|e| TranslationError::InvalidJson(e.to_string()))?;
+    let optional = |v: &Value| {
+        v.as_str()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let entries = |key: &str| match &data[key] {
+        Value::Null => Ok(Vec::new()),
+        Value::Array(items) => Ok(items.clone()),
+        _ => Err(TranslationError::SchemaViolation(format!(
+            "{key} is not an array"
+        ))),
+    };
+    let targets = |v: &Value| -> BTreeMap<String, String> {
+        match v {
+            Value::Object(map) => map
+                .iter()
+                .filter_map(|(tag, text)| {
+                    let tag = locale::resolve(tag).map(|l| l.tag).unwrap_or(tag);
+                    Some((tag.to_string(), optional(text)?))
+                })
+                .collect(),
+            _ => match optional(v) {
+                Some(text) => tags.iter().map(|t| (t.to_string(), text.clone())).collect(),
+                None => BTreeMap::new(),
+            },
+        }
+    };
+    let terms = entries("terms")?
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let source = optional(&v["source"]).ok_or_else(|| {
+                TranslationError::SchemaViolation(format!("term {i} has no source"))
+            })?;
+            Ok(Term {
+                target: targets(&v["target"]),
+                note: optional(&v["note"]),
+                do_not_translate: v["doNotTranslate"].as_bool().unwrap_or(false),
+                source,
+            })
+        })
+        .collect::<Result<Vec<_>, TranslationError>>()?;
+    let characters = entries("characters")?
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let name = optional(&v["name"]).ok_or_else(|| {
+                TranslationError::SchemaViolation(format!("character {i} has no name"))
+            })?;
+            Ok(Character {
+                name,
+                gender: optional(&v["gender"]),
+                formality: optional(&v["formality"]),
+            })
+        })
+        .collect::<Result<Vec<_>, TranslationError>>()?;
+    Ok(Glossary {
+        summary: optional(&data["summary"]).unwrap_or_default(),
+        terms,
+        characters,
+    })
+}
+
+/// Parse the `translatedLines` JSON a model returned.
+/// Text around the outermost braces, such as a Markdown code fence, is ignored.
+/// This function should reject any entry that breaks the schema instead of
+/// skipping it, so a malformed answer never leaves lines silently untranslated.
+pub(crate) fn parse_translated_lines(content: &str) -> Result<Vec<IndexedLine>> {
+    let json = match (content.find('{'), content.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => return Err(TranslationError::InvalidJson("no JSON object".into()).into()),
+    };
+    let data: Value =
+        serde_json::from_str(json).map_err(|e| TranslationError::InvalidJson(e.to_string()))?;
+    let arr = data["translatedLines"]
+        .as_array()
+        .ok_or_else(|| TranslationError::SchemaViolation("missing translatedLines array".into()))?;
+    arr.iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let index = v["index"]
+                .as_str()
+                .and_then(|s| s.trim().parse().ok())
+                .ok_or_else(|| {
+                    TranslationError::SchemaViolation(format!("item {i} has no numeric index"))
+                })?;
+            let text = v["translation"].as_str().ok_or_else(|| {
+                TranslationError::SchemaViolation(format!("item {i} has no translation"))
+            })?;
+            Ok(IndexedLine {
+                index,
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensure every supported locale maps to an example in its own language.
+    #[test]
+    fn every_locale_has_example() {
+        let portuguese = include_str!("prompts/examples/pt.json");
+        for locale in locale::LOCALES {
+            let example = example_output(locale);
+            if locale::primary_subtag(locale.tag) != "pt" {
+                assert_ne!(example, portuguese, "{}", locale.tag);
+            }
+        }
+    }
+
+    /// Ensure the prompts name the source language instead of assuming English.
+    #[test]
+    fn names_source_language() {
+        let lines = [IndexedLine {
+            index: 1,
+            text: "hola".into(),
+        }];
+        let (system, user) = translation("", &[], &lines, "spa", "en").unwrap();
+        assert!(system.contains("You translate Spanish subtitles to English"));
+        assert!(user.contains("'translation' field with Spanish text"));
+        assert!(user.contains(include_str!("prompts/examples/en.json")));
+        assert!(translation("", &[], &lines, "xx", "en").is_err());
+    }
+
+    /// Ensure fenced JSON answers are accepted.
+    #[test]
+    fn parses_fenced_json() {
+        let content =
+            "```json\n{\"translatedLines\": [{\"index\": \"3\", \"translation\": \"oi\"}]}\n```";
+        let lines = parse_translated_lines(content).unwrap();
+        assert_eq!(
+            lines,
+            vec![IndexedLine {
+                index: 3,
+                text: "oi".into()
+            }]
+        );
+    }
+
+    /// Ensure malformed answers become typed errors instead of dropped lines.
+    #[test]
+    fn rejects_schema_violations() {
+        let kind = |content: &str| {
+            parse_translated_lines(content)
+                .unwrap_err()
+                .downcast::<TranslationError>()
+                .unwrap()
+        };
+        assert!(matches!(kind("sorry"), TranslationError::InvalidJson(_)));
+        assert!(matches!(
+            kind("{\"lines\": []}"),
+            TranslationError::SchemaViolation(_)
+        ));
+        assert_eq!(
+            kind("{\"translatedLines\": [{\"index\": \"1\", \"translation\": \"a\"}, {\"index\": \"2\"}]}"),
+            TranslationError::SchemaViolation("item 1 has no translation".into())
+        );
+    }
+
+    /// Ensure glossary answers map to the structured type with one target per
+    /// locale, bad entries are rejected and the schema lists every locale.
+    #[test]
+    fn parses_glossary() {
+        let content = r#"Here it is: {"summary": "A heist.", "terms": [
+            {"source": "The Vault", "target": {"pt-BR": "O Cofre", "es": "La Bóveda"}, "note": null, "doNotTranslate": false},
+            {"source": "Kaito", "target": {"pt-BR": "", "es": ""}, "note": "lead", "doNotTranslate": true},
+            {"source": "Ryu", "target": "Ryu", "note": null, "doNotTranslate": true}
+        ], "characters": [{"name": "Kaito", "gender": "male", "formality": ""}]}"#;
+        let glossary = parse_glossary(content, &["pt-br", "es"]).unwrap();
+        assert_eq!(glossary.summary, "A heist.");
+        assert_eq!(
+            glossary.terms[0].target,
+            BTreeMap::from([
+                ("pt-BR".to_string(), "O Cofre".to_string()),
+                ("es".to_string(), "La Bóveda".to_string())
+            ])
+        );
+        assert_eq!(
+            glossary.terms[1],
+            Term {
+                source: "Kaito".into(),
+                target: BTreeMap::new(),
+                note: Some("lead".into()),
+                do_not_translate: true,
+            }
+        );
+        assert_eq!(glossary.terms[2].target.len(), 2);
+        assert_eq!(
+            glossary.characters,
+            vec![Character {
+                name: "Kaito".into(),
+                gender: Some("male".into()),
+                formality: None,
+            }]
+        );
+        assert_eq!(
+            parse_glossary(r#"{"terms": [{"target": "x"}]}"#, &["es"])
+                .unwrap_err()
+                .downcast::<TranslationError>()
+                .unwrap(),
+            TranslationError::SchemaViolation("term 0 has no source".into())
+        );
+
+        let schema = glossary_schema(&["pt-br", "es"]).unwrap();
+        let target = &schema["properties"]["terms"]["items"]["properties"]["target"];
+        assert_eq!(target["required"], json!(["pt-BR", "es"]));
+        assert_eq!(target["properties"]["es"], json!({"type": "string"}));
+        assert!(super::glossary(&["pt-BR", "es"])
+            .unwrap()
+            .contains("pt-BR, es"));
+    }
+}
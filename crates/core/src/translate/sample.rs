@@ -0,0 +1,211 @@
+//! Choosing the subtitle lines the glossary is built from.
+//! Samples are spread over the whole runtime and favour lines with names and
+//! recurring words, so recaps and credits at the start of a file do not make
+//! up the whole sample.
+
+use super::batch::estimate_tokens;
+use crate::subtitle::Cue;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// Shortest word, in characters, counted as a recurring token.
+const MIN_TOKEN_CHARS: usize = 4;
+
+/// Lines a word must appear in to count as recurring.
+const MIN_TOKEN_LINES: usize = 3;
+
+/// Pick up to `size` lines of `cues` for the glossary, within `token_budget`
+/// estimated tokens, returned in the order they are spoken.
+/// The way this works is by cutting the runtime into `size` equal windows and
+/// taking the best scoring line of each, then filling windows without
+/// dialogue with the best lines left. When the picks exceed the budget the
+/// lowest scoring ones are dropped first.
+pub(crate) fn glossary_sample(cues: &[Cue], size: usize, token_budget: usize) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let lines: Vec<(u64, String)> = cues
+        .iter()
+        .map(|c| (c.start_ms, c.text.join(" ").trim().to_string()))
+        .filter(|(_, text)| !text.is_empty() && seen.insert(text.clone()))
+        .collect();
+    if lines.is_empty() || size == 0 {
+        return Vec::new();
+    }
+    let texts: Vec<&str> = lines.iter().map(|(_, t)| t.as_str()).collect();
+    let scores = score_lines(&texts);
+    let best = |positions: &mut dyn Iterator<Item = usize>| {
+        positions.max_by_key(|&pos| (scores[pos], Reverse(pos)))
+    };
+
+    let first = lines.iter().map(|(ms, _)| *ms).min().unwrap_or(0);
+    let span = lines.iter().map(|(ms, _)| *ms).max().unwrap_or(0) - first + 1;
+    let mut picked = Vec::new();
+    for window in 0..size as u64 {
+        let from = first + span * window / size as u64;
+        let to = first + span * (window + 1) / size as u64;
+        let mut inside = (0..lines.len()).filter(|&pos| (from..to).contains(&lines[pos].0));
+        if let Some(pos) = best(&mut inside) {
+            picked.push(pos);
+        }
+    }
+    while picked.len() < size.min(lines.len()) {
+        let mut rest = (0..lines.len()).filter(|pos| !picked.contains(pos));
+        match best(&mut rest) {
+            Some(pos) => picked.push(pos),
+            None => break,
+        }
+    }
+
+    picked.sort_by_key(|&pos| (Reverse(scores[pos]), pos));
+    let mut tokens = 0;
+    let mut kept: Vec<usize> = Vec::new();
+    for pos in picked {
+        let cost = estimate_tokens(texts[pos]) + 1;
+        if tokens + cost <= token_budget {
+            tokens += cost;
+            kept.push(pos);
+        }
+    }
+    kept.sort_unstable();
+    kept.into_iter().map(|pos| texts[pos].to_string()).collect()
+}
+
+/// Score every line: two points per proper noun and one per recurring word.
+/// Proper nouns are words written capitalized in the middle of a sentence
+/// somewhere in the file and never in lower case. Recurring words appear in
+/// at least `MIN_TOKEN_LINES` lines but in no more than a tenth of them, which
+/// leaves out filler such as "that" or "know".
+fn score_lines(texts: &[&str]) -> Vec<usize> {
+    let words: Vec<Vec<(bool, &str)>> = texts.iter().map(|t| words(t)).collect();
+    let mut capitalized = HashSet::new();
+    let mut lowercase = HashSet::new();
+    let mut line_counts: HashMap<String, usize> = HashMap::new();
+    for line in &words {
+        let mut distinct = HashSet::new();
+        for &(sentence_start, word) in line {
+            if !sentence_start && is_capitalized(word) {
+                capitalized.insert(word.to_lowercase());
+            }
+            if word.chars().next().is_some_and(char::is_lowercase) {
+                lowercase.insert(word.to_lowercase());
+            }
+            if word.chars().count() >= MIN_TOKEN_CHARS {
+                distinct.insert(word.to_lowercase());
+            }
+        }
+        for word in distinct {
+            *line_counts.entry(word).or_default() += 1;
+        }
+    }
+    let max_lines = (texts.len() / 10).max(MIN_TOKEN_LINES);
+    words
+        .iter()
+        .map(|line| {
+            let mut proper = HashSet::new();
+            let mut recurring = HashSet::new();
+            for &(_, word) in line {
+                let lower = word.to_lowercase();
+                if is_capitalized(word)
+                    && capitalized.contains(&lower)
+                    && !lowercase.contains(&lower)
+                {
+                    proper.insert(lower.clone());
+                }
+                let count = line_counts.get(&lower).copied().unwrap_or(0);
+                if (MIN_TOKEN_LINES..=max_lines).contains(&count) {
+                    recurring.insert(lower);
+                }
+            }
+            2 * proper.len() + recurring.len()
+        })
+        .collect()
+}
+
+/// Split `text` into words, each flagged when it starts a sentence.
+fn words(text: &str) -> Vec<(bool, &str)> {
+    let mut out = Vec::new();
+    let mut sentence_start = true;
+    let mut rest = text;
+    while let Some(begin) = rest.find(char::is_alphanumeric) {
+        if rest[..begin].contains(['.', '!', '?']) {
+            sentence_start = true;
+        }
+        let len = rest[begin..]
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(rest.len() - begin);
+        out.push((sentence_start, &rest[begin..begin + len]));
+        sentence_start = false;
+        rest = &rest[begin + len..];
+    }
+    out
+}
+
+/// Whether `word` starts with a capital followed by lower case, so neither
+/// `I` nor shouted words count.
+fn is_capitalized(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(char::is_uppercase) && chars.any(char::is_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(start_ms: u64, text: &str) -> Cue {
+        Cue {
+            index: 0,
+            start_ms,
+            end_ms: start_ms + 1000,
+            text: vec![text.to_string()],
+            style: None,
+            position: None,
+        }
+    }
+
+    /// Ensure proper nouns and recurring words raise the score, but sentence
+    /// starts and filler do not.
+    #[test]
+    fn scores_names_and_recurring_words() {
+        let texts = [
+            "Where is the Vault, Kaito?",
+            "Kaito went to the vault.",
+            "The vault is locked.",
+            "Nice weather.",
+            "Where are you going?",
+        ];
+        let scores = score_lines(&texts);
+        assert_eq!(scores, vec![3, 3, 1, 0, 0]);
+        assert_eq!(
+            words("- Hi. I'm Kaito!"),
+            vec![(true, "Hi"), (true, "I"), (false, "m"), (false, "Kaito")]
+        );
+    }
+
+    /// Ensure samples span the runtime, prefer names, and respect the size
+    /// and token budget.
+    #[test]
+    fn spreads_sample_over_runtime() {
+        let mut cues: Vec<Cue> = (0..20)
+            .map(|i| cue(i * 1000, "Previously on the show."))
+            .collect();
+        cues.push(cue(600_000, "Where is the ring, Frodo?"));
+        cues.push(cue(600_500, "Okay."));
+        cues.push(cue(1_200_000, "Thank you, Sam."));
+        cues.push(cue(1_800_000, "Frodo, wait!"));
+
+        let sample = glossary_sample(&cues, 4, 1000);
+        assert_eq!(
+            sample,
+            [
+                "Previously on the show.",
+                "Where is the ring, Frodo?",
+                "Thank you, Sam.",
+                "Frodo, wait!"
+            ]
+        );
+        assert_eq!(glossary_sample(&cues, 10, 1000).len(), 5);
+        let tight = glossary_sample(&cues, 4, 12);
+        assert_eq!(tight, ["Where is the ring, Frodo?", "Frodo, wait!"]);
+        assert!(glossary_sample(&cues, 0, 1000).is_empty());
+        assert!(glossary_sample(&[], 4, 1000).is_empty());
+    }
+}
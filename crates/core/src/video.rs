@@ -1,58 +1,279 @@
 //! Video helpers for working with subtitles.
 
+use crate::locale::{self, Language};
 use anyhow::{anyhow, Result};
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use tracing::trace;
+use std::str::FromStr;
+use tracing::{info, trace};
 
-/// Represents a subtitle stream returned by ffprobe.
-/// This type holds optional language and title tags.
+/// Describes a subtitle stream found in a video file.
+/// This is what `list_subtitle_streams` reports for every track ffprobe sees.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubtitleStream {
+    /// Absolute stream index inside the container.
+    pub index: usize,
+    /// Codec name such as `subrip`, `ass` or `hdmv_pgs_subtitle`.
+    pub codec: Option<String>,
+    /// Language tag, usually an ISO 639-2 code like `eng`.
+    pub language: Option<String>,
+    /// Free-form title tag, e.g. `English SDH`.
+    pub title: Option<String>,
+    /// Disposition flags set by the muxer.
+    pub disposition: Disposition,
+    /// Number of subtitle events when the container records it.
+    pub frames: Option<u64>,
+}
+
+/// Disposition flags of a subtitle stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Disposition {
+    /// The player selects this stream by default.
+    pub default: bool,
+    /// The stream only covers foreign or on-screen text.
+    pub forced: bool,
+    /// The stream is meant for hearing impaired viewers (SDH).
+    pub hearing_impaired: bool,
+}
+
+/// Represents a subtitle stream as returned by ffprobe's JSON output.
+/// ffprobe may omit most of these fields, so they are optional.
 #[derive(Debug, Deserialize)]
-struct Stream {
+struct RawStream {
+    index: usize,
+    codec_name: Option<String>,
+    nb_frames: Option<String>,
+    #[serde(default)]
+    disposition: RawDisposition,
     #[serde(default)]
     tags: Tags,
 }
 
-/// Captures the language and title tags for a stream.
-/// ffprobe may omit these fields, so they are optional.
+/// Captures the disposition flags, which ffprobe reports as `0` or `1`.
+#[derive(Debug, Default, Deserialize)]
+struct RawDisposition {
+    #[serde(default)]
+    default: u8,
+    #[serde(default)]
+    forced: u8,
+    #[serde(default)]
+    hearing_impaired: u8,
+}
+
+/// Captures the language, title and statistics tags for a stream.
+/// Matroska files store the frame count as a `NUMBER_OF_FRAMES` statistics tag;
+/// mkvmerge often writes it a second time with an `-eng` suffix.
 #[derive(Debug, Default, Deserialize)]
 struct Tags {
     language: Option<String>,
     title: Option<String>,
+    #[serde(rename = "NUMBER_OF_FRAMES")]
+    number_of_frames: Option<String>,
+    #[serde(rename = "NUMBER_OF_FRAMES-eng")]
+    number_of_frames_eng: Option<String>,
+}
+
+impl From<RawStream> for SubtitleStream {
+    fn from(raw: RawStream) -> Self {
+        // ffprobe reports `N/A` for `nb_frames` when unknown, so each value is
+        // parsed before falling back to the next one.
+        let parse = |n: Option<&str>| n.and_then(|n| n.trim().parse().ok());
+        let frames = parse(raw.nb_frames.as_deref())
+            .or_else(|| parse(raw.tags.number_of_frames.as_deref()))
+            .or_else(|| parse(raw.tags.number_of_frames_eng.as_deref()));
+        Self {
+            index: raw.index,
+            codec: raw.codec_name,
+            language: raw.tags.language,
+            title: raw.tags.title,
+            disposition: Disposition {
+                default: raw.disposition.default != 0,
+                forced: raw.disposition.forced != 0,
+                hearing_impaired: raw.disposition.hearing_impaired != 0,
+            },
+            frames,
+        }
+    }
+}
+
+/// Kind of subtitle track, derived from dispositions and titles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StreamKind {
+    /// Regular dialogue track.
+    Full,
+    /// Closed captions for hearing impaired viewers, with sound cues.
+    Sdh,
+    /// Only foreign dialogue and on-screen text.
+    Forced,
+}
+
+/// Which kind of track automatic selection favours.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum StreamPolicy {
+    /// Regular dialogue first, then SDH; forced tracks last.
+    PreferFull,
+    /// SDH/closed captions first, then regular dialogue; forced tracks last.
+    #[default]
+    PreferSdh,
+    /// Forced tracks first, then regular dialogue, then SDH.
+    PreferForced,
+}
+
+impl StreamPolicy {
+    /// Rank a stream kind under this policy; higher wins.
+    fn rank(self, kind: StreamKind) -> u8 {
+        match (self, kind) {
+            (StreamPolicy::PreferFull, StreamKind::Full) => 2,
+            (StreamPolicy::PreferFull, StreamKind::Sdh) => 1,
+            (StreamPolicy::PreferSdh, StreamKind::Sdh) => 2,
+            (StreamPolicy::PreferSdh, StreamKind::Full) => 1,
+            (StreamPolicy::PreferForced, StreamKind::Forced) => 2,
+            (StreamPolicy::PreferForced, StreamKind::Full) => 1,
+            _ => 0,
+        }
+    }
+}
+
+impl FromStr for StreamPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "prefer-full" => Ok(StreamPolicy::PreferFull),
+            "prefer-sdh" => Ok(StreamPolicy::PreferSdh),
+            "prefer-forced" => Ok(StreamPolicy::PreferForced),
+            _ => Err(anyhow!(
+                "unknown stream policy {s}; expected prefer-full, prefer-sdh or prefer-forced"
+            )),
+        }
+    }
+}
+
+/// How the subtitle stream to extract is chosen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamSelection {
+    /// Rank the streams in the source language with the policy and take the best one.
+    Auto(StreamPolicy),
+    /// Use the stream with this absolute index, as printed by `probe`.
+    Index(usize),
+    /// Use the first stream whose title contains this text (case-insensitive).
+    Title(String),
+}
+
+impl Default for StreamSelection {
+    fn default() -> Self {
+        StreamSelection::Auto(StreamPolicy::default())
+    }
+}
+
+/// File format an extracted subtitle track is written in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtractFormat {
+    /// Transcode to SRT, dropping styling.
+    Srt,
+    /// Copy an ASS/SSA track as is, keeping styles and override tags.
+    Ass,
+}
+
+impl ExtractFormat {
+    /// Pick the format for a stream codec: ASS/SSA tracks are copied, everything else becomes SRT.
+    pub fn for_codec(codec: Option<&str>) -> Self {
+        match codec {
+            Some(c) if c.eq_ignore_ascii_case("ass") || c.eq_ignore_ascii_case("ssa") => {
+                ExtractFormat::Ass
+            }
+            _ => ExtractFormat::Srt,
+        }
+    }
+
+    /// File extension used for this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExtractFormat::Srt => "srt",
+            ExtractFormat::Ass => "ass",
+        }
+    }
 }
 
 /// Build the ffmpeg arguments to extract a subtitle track and the output path.
-/// This delegates the choice of stream to the caller via `stream_index`.
-pub fn ffmpeg_extract_args(input: &Path, stream_index: usize) -> (PathBuf, Vec<String>) {
+/// This delegates the choice of stream to the caller via `stream_index`, and
+/// names the output after `lang_code`, e.g. `<stem>_en.srt`.
+pub fn ffmpeg_extract_args(
+    input: &Path,
+    stream_index: usize,
+    lang_code: &str,
+    format: ExtractFormat,
+) -> (PathBuf, Vec<String>) {
     let stem = input
         .file_stem()
         .unwrap_or_default()
         .to_string_lossy()
         .to_string();
-    let out = input.with_file_name(format!("{}_en.srt", stem));
+    let out = input.with_file_name(format!("{}_{}.{}", stem, lang_code, format.extension()));
+    let codec = match format {
+        ExtractFormat::Srt => "srt",
+        ExtractFormat::Ass => "copy",
+    };
     let args = vec![
         "-i".to_string(),
         input.display().to_string(),
         "-map".to_string(),
         format!("0:s:{}", stream_index),
         "-c:s".to_string(),
-        "srt".to_string(),
+        codec.to_string(),
         out.display().to_string(),
     ];
     (out, args)
 }
 
+/// Extract subtitles in `source_lang` from `path` using ffmpeg.
+/// The language may be given as an ISO 639-1, 639-2/B or 639-2/T code or by
+/// name (`en`, `eng`, `English`). This probes available subtitle streams, picks
+/// the best track in that language and then calls ffmpeg to copy it to an SRT
+/// file named after the language. ASS/SSA tracks are copied to an `.ass`
+/// file instead so their styling survives.
+pub fn extract_subtitles(path: &Path, source_lang: &str) -> Result<PathBuf> {
+    extract_subtitles_with(path, source_lang, &StreamSelection::default())
+}
+
 /// Extract English subtitles from `path` using ffmpeg.
-/// This probes available subtitle streams, picks the best English track and
-/// then calls ffmpeg to copy it to an SRT file.
+/// Kept for existing callers; see `extract_subtitles` for other languages.
 pub fn extract_english_subtitles(path: &Path) -> Result<PathBuf> {
+    extract_subtitles(path, "en")
+}
+
+/// Extract subtitles from `path`, letting the caller bypass the automatic
+/// stream choice with `selection`.
+/// When a stream is forced the output is named after that stream's language
+/// tag, falling back to `source_lang` for untagged streams.
+pub fn extract_subtitles_with(
+    path: &Path,
+    source_lang: &str,
+    selection: &StreamSelection,
+) -> Result<PathBuf> {
     trace!(
-        "extract_english_subtitles(path={}): invoking ffmpeg",
-        path.display()
+        "extract_subtitles_with(path={}, source_lang={}, selection={:?}): invoking ffmpeg",
+        path.display(),
+        source_lang,
+        selection
     );
-    let index = pick_subtitle_index(path)?;
-    let (out, args) = ffmpeg_extract_args(path, index);
+    let language = locale::resolve_language(source_lang)?;
+    let streams = list_subtitle_streams(path)?;
+    let pos = select_stream(&streams, language, selection)?;
+    let lang_code = streams[pos]
+        .language
+        .as_deref()
+        .and_then(locale::find_language)
+        .unwrap_or(language)
+        .iso1;
+    info!(
+        "extracting subtitle stream {} ({})",
+        streams[pos].index,
+        streams[pos].title.as_deref().unwrap_or("untitled")
+    );
+    let format = ExtractFormat::for_codec(streams[pos].codec.as_deref());
+    let (out, args) = ffmpeg_extract_args(path, pos, lang_code, format);
     let status = Command::new("ffmpeg").args(&args).status()?;
     if !status.success() {
         return Err(anyhow!("ffmpeg failed"));
@@ -60,29 +281,33 @@ pub fn extract_english_subtitles(path: &Path) -> Result<PathBuf> {
     Ok(out)
 }
 
-/// Decide which English subtitle stream to extract.
-/// The way this works is by scoring English streams based on their title
-/// and picking the one that looks most like a closed caption track.
-fn best_english_stream(streams: &[Stream]) -> Option<usize> {
-    let mut best: Option<(usize, i32)> = None;
+/// Decide which subtitle stream in `language` to extract.
+/// The way this works is by classifying streams tagged with any alias of the
+/// language as full, SDH or forced from their disposition and title, ranking
+/// the kinds according to `policy`, and breaking ties by frame count and the
+/// default flag so a sparse track never beats a complete one of the same kind.
+/// It returns the position of the stream among the subtitle streams, which is
+/// what ffmpeg's `0:s:N` mapping expects.
+pub fn best_stream(
+    streams: &[SubtitleStream],
+    language: &Language,
+    policy: StreamPolicy,
+) -> Option<usize> {
+    let mut best: Option<(usize, (u8, u64, bool))> = None;
     for (i, stream) in streams.iter().enumerate() {
         let lang = stream
-            .tags
             .language
             .as_deref()
-            .map(|s| s.eq_ignore_ascii_case("eng"))
+            .map(|s| language.matches(s))
             .unwrap_or(false);
         if !lang {
             continue;
         }
-        let title = stream.tags.title.as_deref().unwrap_or("").to_lowercase();
-        let score = if title.contains("cc") || title.contains("sdh") || title.contains("caption") {
-            2
-        } else if title.contains("sub") {
-            1
-        } else {
-            0
-        };
+        let score = (
+            policy.rank(stream_kind(stream)),
+            stream.frames.unwrap_or(0),
+            stream.disposition.default,
+        );
         match best {
             Some((_, best_score)) if score <= best_score => {}
             _ => best = Some((i, score)),
@@ -91,11 +316,28 @@ fn best_english_stream(streams: &[Stream]) -> Option<usize> {
     best.map(|(idx, _)| idx)
 }
 
-/// Probe subtitle streams with ffprobe and pick the best English track.
-/// It returns the stream index to map with ffmpeg.
-fn pick_subtitle_index(path: &Path) -> Result<usize> {
+/// Classify a stream from its disposition flags, falling back to title hints.
+fn stream_kind(stream: &SubtitleStream) -> StreamKind {
+    let title = stream.title.as_deref().unwrap_or("").to_lowercase();
+    if stream.disposition.forced || title.contains("forced") {
+        StreamKind::Forced
+    } else if stream.disposition.hearing_impaired
+        || title.contains("cc")
+        || title.contains("sdh")
+        || title.contains("caption")
+    {
+        StreamKind::Sdh
+    } else {
+        StreamKind::Full
+    }
+}
+
+/// List every subtitle stream of `path` using ffprobe.
+/// Streams are returned in container order, so the position in the list is the
+/// relative subtitle index ffmpeg maps with `0:s:N`.
+pub fn list_subtitle_streams(path: &Path) -> Result<Vec<SubtitleStream>> {
     trace!(
-        "pick_subtitle_index(path={}): listing subtitle streams",
+        "list_subtitle_streams(path={}): invoking ffprobe",
         path.display()
     );
     let output = Command::new("ffprobe")
@@ -105,7 +347,7 @@ fn pick_subtitle_index(path: &Path) -> Result<usize> {
             "-select_streams",
             "s",
             "-show_entries",
-            "stream_tags=language,title",
+            "stream=index,codec_name,nb_frames:stream_tags:stream_disposition=default,forced,hearing_impaired",
             "-of",
             "json",
             path.to_string_lossy().as_ref(),
@@ -114,15 +356,59 @@ fn pick_subtitle_index(path: &Path) -> Result<usize> {
     if !output.status.success() {
         return Err(anyhow!("ffprobe failed"));
     }
+    parse_streams(&output.stdout)
+}
+
+/// Parse ffprobe's JSON stream listing into subtitle streams.
+fn parse_streams(json: &[u8]) -> Result<Vec<SubtitleStream>> {
     #[derive(Deserialize)]
     struct Streams {
-        streams: Vec<Stream>,
+        #[serde(default)]
+        streams: Vec<RawStream>,
     }
-    let data: Streams = serde_json::from_slice(&output.stdout)?;
-    if let Some(idx) = best_english_stream(&data.streams) {
-        Ok(idx)
-    } else {
-        Err(anyhow!("no english subtitles found"))
+    let data: Streams = serde_json::from_slice(json)?;
+    Ok(data.streams.into_iter().map(SubtitleStream::from).collect())
+}
+
+/// Apply `selection` to the probed `streams`.
+/// It returns the position of the chosen stream among the subtitle streams.
+pub fn select_stream(
+    streams: &[SubtitleStream],
+    language: &Language,
+    selection: &StreamSelection,
+) -> Result<usize> {
+    match selection {
+        StreamSelection::Auto(policy) => best_stream(streams, language, *policy)
+            .ok_or_else(|| anyhow!("no {} subtitles found", language.name)),
+        StreamSelection::Index(index) => {
+            streams
+                .iter()
+                .position(|s| s.index == *index)
+                .ok_or_else(|| {
+                    let known: Vec<String> = streams.iter().map(|s| s.index.to_string()).collect();
+                    anyhow!(
+                        "no subtitle stream with index {index}; available: {}",
+                        known.join(", ")
+                    )
+                })
+        }
+        StreamSelection::Title(text) => {
+            let text = text.to_lowercase();
+            let matching = |s: &SubtitleStream| {
+                s.title
+                    .as_deref()
+                    .map(|t| t.to_lowercase().contains(&text))
+                    .unwrap_or(false)
+            };
+            // Prefer a title match in the source language, then any title match.
+            streams
+                .iter()
+                .position(|s| {
+                    matching(s) && s.language.as_deref().is_some_and(|l| language.matches(l))
+                })
+                .or_else(|| streams.iter().position(matching))
+                .ok_or_else(|| anyhow!("no subtitle stream title contains {text:?}"))
+        }
     }
 }
 
@@ -134,7 +420,7 @@ mod tests {
     #[test]
     fn builds_expected_ffmpeg_args() {
         let input = Path::new("foo.mkv");
-        let (out, args) = ffmpeg_extract_args(input, 3);
+        let (out, args) = ffmpeg_extract_args(input, 3, "en", ExtractFormat::Srt);
         assert_eq!(out, PathBuf::from("foo_en.srt"));
         let expected = [
             "-i",
@@ -151,22 +437,201 @@ mod tests {
         assert_eq!(args, expected);
     }
 
+    /// Ensure ASS tracks are copied instead of transcoded to SRT.
+    #[test]
+    fn copies_ass_tracks() {
+        let format = ExtractFormat::for_codec(Some("ass"));
+        assert_eq!(format, ExtractFormat::Ass);
+        assert_eq!(ExtractFormat::for_codec(Some("subrip")), ExtractFormat::Srt);
+        let (out, args) = ffmpeg_extract_args(Path::new("foo.mkv"), 0, "ja", format);
+        assert_eq!(out, PathBuf::from("foo_ja.ass"));
+        assert_eq!(args[4..6], ["-c:s".to_string(), "copy".to_string()]);
+    }
+
     #[test]
     fn picks_cc_stream_over_plain() {
         let streams = vec![
-            Stream {
-                tags: Tags {
-                    language: Some("eng".to_string()),
-                    title: Some("English".to_string()),
+            SubtitleStream {
+                language: Some("eng".to_string()),
+                title: Some("English".to_string()),
+                ..Default::default()
+            },
+            SubtitleStream {
+                language: Some("eng".to_string()),
+                title: Some("English CC".to_string()),
+                ..Default::default()
+            },
+        ];
+        let english = locale::find_language("en").unwrap();
+        assert_eq!(
+            best_stream(&streams, english, StreamPolicy::PreferSdh),
+            Some(1)
+        );
+    }
+
+    /// Ensure a Japanese source matches streams tagged with either ISO 639 code.
+    #[test]
+    fn picks_stream_in_source_language() {
+        let streams = vec![
+            SubtitleStream {
+                language: Some("eng".to_string()),
+                title: Some("English".to_string()),
+                ..Default::default()
+            },
+            SubtitleStream {
+                language: Some("jpn".to_string()),
+                title: None,
+                ..Default::default()
+            },
+        ];
+        let japanese = locale::find_language("Japanese").unwrap();
+        assert_eq!(
+            best_stream(&streams, japanese, StreamPolicy::default()),
+            Some(1)
+        );
+        let korean = locale::find_language("kor").unwrap();
+        assert_eq!(best_stream(&streams, korean, StreamPolicy::default()), None);
+    }
+
+    /// Ensure forced tracks never win by title alone and policies reorder the kinds.
+    #[test]
+    fn ranks_streams_by_policy() {
+        let streams = vec![
+            SubtitleStream {
+                index: 2,
+                language: Some("eng".to_string()),
+                title: Some("English Forced subs".to_string()),
+                disposition: Disposition {
+                    forced: true,
+                    ..Default::default()
                 },
+                frames: Some(30),
+                ..Default::default()
             },
-            Stream {
-                tags: Tags {
-                    language: Some("eng".to_string()),
-                    title: Some("English CC".to_string()),
+            SubtitleStream {
+                index: 3,
+                language: Some("eng".to_string()),
+                title: Some("English".to_string()),
+                frames: Some(1200),
+                ..Default::default()
+            },
+            SubtitleStream {
+                index: 4,
+                language: Some("eng".to_string()),
+                disposition: Disposition {
+                    hearing_impaired: true,
+                    ..Default::default()
                 },
+                frames: Some(1500),
+                ..Default::default()
             },
         ];
-        assert_eq!(best_english_stream(&streams), Some(1));
+        let english = locale::find_language("en").unwrap();
+        let pick = |policy| best_stream(&streams, english, policy);
+        assert_eq!(pick(StreamPolicy::PreferFull), Some(1));
+        assert_eq!(pick(StreamPolicy::PreferSdh), Some(2));
+        assert_eq!(pick(StreamPolicy::PreferForced), Some(0));
+        assert_eq!(
+            "prefer-full".parse::<StreamPolicy>().unwrap(),
+            StreamPolicy::PreferFull
+        );
+        assert!("prefer-none".parse::<StreamPolicy>().is_err());
+    }
+
+    /// Ensure the frame count breaks ties between tracks of the same kind.
+    #[test]
+    fn prefers_stream_with_more_frames() {
+        let streams = vec![
+            SubtitleStream {
+                language: Some("eng".to_string()),
+                title: Some("Signs".to_string()),
+                frames: Some(40),
+                ..Default::default()
+            },
+            SubtitleStream {
+                language: Some("eng".to_string()),
+                title: Some("Dialogue".to_string()),
+                frames: Some(900),
+                ..Default::default()
+            },
+        ];
+        let english = locale::find_language("en").unwrap();
+        assert_eq!(
+            best_stream(&streams, english, StreamPolicy::PreferFull),
+            Some(1)
+        );
+    }
+
+    /// Ensure manual selection can force a track by absolute index or title.
+    #[test]
+    fn selects_stream_manually() {
+        let streams = vec![
+            SubtitleStream {
+                index: 2,
+                language: Some("eng".to_string()),
+                title: Some("English".to_string()),
+                ..Default::default()
+            },
+            SubtitleStream {
+                index: 3,
+                language: Some("eng".to_string()),
+                title: Some("English SDH".to_string()),
+                ..Default::default()
+            },
+        ];
+        let english = locale::find_language("en").unwrap();
+        assert_eq!(
+            select_stream(&streams, english, &StreamSelection::default()).unwrap(),
+            1
+        );
+        assert_eq!(
+            select_stream(&streams, english, &StreamSelection::Index(2)).unwrap(),
+            0
+        );
+        assert_eq!(
+            select_stream(&streams, english, &StreamSelection::Title("sdh".into())).unwrap(),
+            1
+        );
+        assert!(select_stream(&streams, english, &StreamSelection::Index(7)).is_err());
+        assert!(select_stream(&streams, english, &StreamSelection::Title("x".into())).is_err());
+    }
+
+    /// Ensure ffprobe output is mapped to streams with dispositions and frame
+    /// counts, with both frame tags present and `N/A` counts skipped.
+    #[test]
+    fn parses_ffprobe_streams() {
+        let json = br#"{
+            "streams": [
+                {
+                    "index": 2,
+                    "codec_name": "subrip",
+                    "disposition": {"default": 1, "forced": 0, "hearing_impaired": 0},
+                    "tags": {"language": "eng", "NUMBER_OF_FRAMES-eng": "1203"}
+                },
+                {
+                    "index": 3,
+                    "codec_name": "ass",
+                    "nb_frames": "31",
+                    "disposition": {"default": 0, "forced": 1, "hearing_impaired": 0},
+                    "tags": {"language": "eng", "title": "Forced"}
+                },
+                {
+                    "index": 4,
+                    "codec_name": "subrip",
+                    "nb_frames": "N/A",
+                    "tags": {"NUMBER_OF_FRAMES": "88", "NUMBER_OF_FRAMES-eng": "88"}
+                }
+            ]
+        }"#;
+        let streams = parse_streams(json).unwrap();
+        assert_eq!(streams.len(), 3);
+        assert_eq!(streams[0].index, 2);
+        assert_eq!(streams[0].codec.as_deref(), Some("subrip"));
+        assert!(streams[0].disposition.default);
+        assert_eq!(streams[0].frames, Some(1203));
+        assert_eq!(streams[1].title.as_deref(), Some("Forced"));
+        assert!(streams[1].disposition.forced);
+        assert_eq!(streams[1].frames, Some(31));
+        assert_eq!(streams[2].frames, Some(88));
     }
 }
@@ -0,0 +1,315 @@
+//! This module is responsible for WebVTT parsing and formatting.
+//! It keeps headers, cue identifiers, cue settings and NOTE/STYLE/REGION blocks
+//! so a translated file can be written back without losing anything but text.
+
+use crate::subtitle::Cue;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// A parsed WebVTT file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VttDocument {
+    /// The `WEBVTT` signature line followed by any header lines.
+    pub header: Vec<String>,
+    /// Blocks in file order.
+    pub blocks: Vec<VttBlock>,
+}
+
+/// A block of a WebVTT file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VttBlock {
+    /// A timed cue with text.
+    Cue(VttCue),
+    /// A `NOTE` comment, kept verbatim.
+    Note(Vec<String>),
+    /// A `STYLE` block with CSS, kept verbatim.
+    Style(Vec<String>),
+    /// A `REGION` definition, kept verbatim.
+    Region(Vec<String>),
+}
+
+/// A single WebVTT cue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VttCue {
+    /// Optional cue identifier line.
+    pub identifier: Option<String>,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Cue settings such as `align:start` or `position:10%`, in file order.
+    pub settings: Vec<String>,
+    pub text: Vec<String>,
+}
+
+impl VttDocument {
+    /// Iterate over the cues, skipping comments and style blocks.
+    pub fn cues(&self) -> impl Iterator<Item = &VttCue> {
+        self.blocks.iter().filter_map(|b| match b {
+            VttBlock::Cue(cue) => Some(cue),
+            _ => None,
+        })
+    }
+
+    /// Convert the cues to format-neutral cues numbered from 1 in file order.
+    /// Cue settings are kept as the cue position.
+    pub fn to_cues(&self) -> Vec<Cue> {
+        self.cues()
+            .enumerate()
+            .map(|(i, cue)| Cue {
+                index: i as u32 + 1,
+                start_ms: cue.start_ms,
+                end_ms: cue.end_ms,
+                text: cue.text.clone(),
+                style: None,
+                position: (!cue.settings.is_empty()).then(|| cue.settings.join(" ")),
+            })
+            .collect()
+    }
+
+    /// Return a copy of the document with cue texts replaced by `cues`.
+    /// Cues are matched by the index `to_cues` assigned.
+    pub fn with_cues(&self, cues: &[Cue]) -> VttDocument {
+        let by_index: HashMap<u32, &Cue> = cues.iter().map(|c| (c.index, c)).collect();
+        let mut doc = self.clone();
+        let mut position = 0u32;
+        for block in doc.blocks.iter_mut() {
+            if let VttBlock::Cue(cue) = block {
+                position += 1;
+                if let Some(translated) = by_index.get(&position) {
+                    cue.text = translated.text.clone();
+                }
+            }
+        }
+        doc
+    }
+}
+
+/// Parse WebVTT text into a document.
+/// This function should accept a UTF-8 BOM, `\r\n` line endings and both
+/// `mm:ss.ttt` and `hh:mm:ss.ttt` timestamps.
+pub fn parse(input: &str) -> Result<VttDocument> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let mut lines = input.lines().map(|l| l.trim_end_matches('\r'));
+    let signature = lines.next().ok_or_else(|| anyhow!("empty WebVTT file"))?;
+    if !is_signature(signature) {
+        return Err(anyhow!("missing WEBVTT signature"));
+    }
+    let mut header = vec![signature.to_string()];
+    for line in lines.by_ref() {
+        if line.trim().is_empty() {
+            break;
+        }
+        header.push(line.to_string());
+    }
+
+    let mut blocks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in lines.chain(std::iter::once("")) {
+        if !line.trim().is_empty() {
+            current.push(line);
+            continue;
+        }
+        if !current.is_empty() {
+            blocks.push(parse_block(&current)?);
+            current.clear();
+        }
+    }
+    Ok(VttDocument { header, blocks })
+}
+
+/// Format a document back to WebVTT text.
+/// The way this works is by writing the header and each block separated by blank lines.
+/// Cue text goes through `payload_lines`, so translated text cannot end a cue
+/// early or start a new one.
+pub fn format(doc: &VttDocument) -> String {
+    let mut out = doc.header.join("\n");
+    out.push_str("\n\n");
+    for block in &doc.blocks {
+        match block {
+            VttBlock::Cue(cue) => {
+                if let Some(id) = &cue.identifier {
+                    out.push_str(id);
+                    out.push('\n');
+                }
+                out.push_str(&format!(
+                    "{} --> {}",
+                    format_time(cue.start_ms),
+                    format_time(cue.end_ms)
+                ));
+                for setting in &cue.settings {
+                    out.push(' ');
+                    out.push_str(setting);
+                }
+                out.push('\n');
+                for line in payload_lines(&cue.text) {
+                    out.push_str(&line);
+                    out.push('\n');
+                }
+            }
+            VttBlock::Note(lines) | VttBlock::Style(lines) | VttBlock::Region(lines) => {
+                for line in lines {
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Make cue text safe to write as a cue payload.
+/// A blank line would end the cue and `-->` would turn a line into a timing
+/// line, so blank lines are dropped and `-->` is written as `--&gt;`.
+fn payload_lines(text: &[String]) -> Vec<String> {
+    text.iter()
+        .flat_map(|line| line.lines())
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.replace("-->", "--&gt;"))
+        .collect()
+}
+
+/// Check for the `WEBVTT` signature, optionally followed by a space or tab and text.
+fn is_signature(line: &str) -> bool {
+    match line.strip_prefix("WEBVTT") {
+        Some(rest) => rest.is_empty() || rest.starts_with([' ', '\t']),
+        None => false,
+    }
+}
+
+/// Check whether `line` starts a block introduced by `keyword`, e.g. `NOTE`.
+fn starts_block(line: &str, keyword: &str) -> bool {
+    match line.strip_prefix(keyword) {
+        Some(rest) => rest.is_empty() || rest.starts_with([' ', '\t']),
+        None => false,
+    }
+}
+
+/// Parse the lines of one block into a cue or a verbatim block.
+fn parse_block(lines: &[&str]) -> Result<VttBlock> {
+    let owned = || lines.iter().map(|l| l.to_string()).collect();
+    let first = lines[0];
+    if starts_block(first, "NOTE") {
+        return Ok(VttBlock::Note(owned()));
+    }
+    if starts_block(first, "STYLE") {
+        return Ok(VttBlock::Style(owned()));
+    }
+    if starts_block(first, "REGION") {
+        return Ok(VttBlock::Region(owned()));
+    }
+    let (identifier, rest) = if first.contains("-->") {
+        (None, lines)
+    } else {
+        (Some(first.to_string()), &lines[1..])
+    };
+    let timing = rest
+        .first()
+        .ok_or_else(|| anyhow!("cue {first:?} has no timing line"))?;
+    let (start_ms, end_ms, settings) = parse_timing(timing)?;
+    Ok(VttBlock::Cue(VttCue {
+        identifier,
+        start_ms,
+        end_ms,
+        settings,
+        text: rest[1..].iter().map(|l| l.to_string()).collect(),
+    }))
+}
+
+/// Parse `00:01.000 --> 00:02.000 align:start` into times and settings.
+fn parse_timing(line: &str) -> Result<(u64, u64, Vec<String>)> {
+    let (start, rest) = line
+        .split_once("-->")
+        .ok_or_else(|| anyhow!("bad cue timing {line:?}"))?;
+    let mut parts = rest.split_whitespace();
+    let end = parts
+        .next()
+        .ok_or_else(|| anyhow!("bad cue timing {line:?}"))?;
+    let settings = parts.map(|s| s.to_string()).collect();
+    Ok((parse_time(start.trim())?, parse_time(end)?, settings))
+}
+
+/// Parse `hh:mm:ss.ttt` or `mm:ss.ttt` into milliseconds.
+fn parse_time(t: &str) -> Result<u64> {
+    let (clock, ms) = t.split_once('.').ok_or_else(|| anyhow!("bad time {t:?}"))?;
+    let fields = clock
+        .split(':')
+        .map(|p| p.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| anyhow!("bad time {t:?}"))?;
+    let (h, m, s) = match fields[..] {
+        [h, m, s] => (h, m, s),
+        [m, s] => (0, m, s),
+        _ => return Err(anyhow!("bad time {t:?}")),
+    };
+    let ms: u64 = ms.parse().map_err(|_| anyhow!("bad time {t:?}"))?;
+    Ok(((h * 60 + m) * 60 + s) * 1000 + ms)
+}
+
+/// Format milliseconds back to `HH:MM:SS.mmm`.
+fn format_time(ms: u64) -> String {
+    let h = ms / 3_600_000;
+    let m = (ms % 3_600_000) / 60_000;
+    let s = (ms % 60_000) / 1000;
+    let ms = ms % 1000;
+    format!("{h:02}:{m:02}:{s:02}.{ms:03}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "WEBVTT - sample\nKind: captions\n\nSTYLE\n::cue { color: yellow }\n\nNOTE translated by hand\n\nintro\n00:00:01.000 --> 00:00:02.500 align:start position:10%\n<i>Hello</i>\nthere\n\n00:03.000 --> 00:04.000\nBye\n\n";
+
+    /// Ensure headers, identifiers, settings and verbatim blocks survive a roundtrip.
+    #[test]
+    fn roundtrip_vtt() {
+        let doc = parse(SAMPLE).unwrap();
+        assert_eq!(doc.header, vec!["WEBVTT - sample", "Kind: captions"]);
+        assert!(matches!(doc.blocks[0], VttBlock::Style(_)));
+        assert!(matches!(doc.blocks[1], VttBlock::Note(_)));
+        let cues: Vec<&VttCue> = doc.cues().collect();
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].identifier.as_deref(), Some("intro"));
+        assert_eq!(cues[0].settings, vec!["align:start", "position:10%"]);
+        assert_eq!(cues[0].end_ms, 2500);
+        assert_eq!(cues[1].start_ms, 3000);
+        let out = format(&doc);
+        assert_eq!(parse(&out).unwrap(), doc);
+        assert!(out.contains("00:00:01.000 --> 00:00:02.500 align:start position:10%\n"));
+    }
+
+    /// Ensure translated texts replace cue texts while keeping settings.
+    #[test]
+    fn replaces_cue_texts() {
+        let doc = parse(SAMPLE).unwrap();
+        let mut cues = doc.to_cues();
+        assert_eq!(cues[1].index, 2);
+        assert_eq!(
+            cues[0].position.as_deref(),
+            Some("align:start position:10%")
+        );
+        cues[0].text = vec!["<i>Olá</i>".into()];
+        let out = format(&doc.with_cues(&cues));
+        assert!(out.contains("align:start position:10%\n<i>Olá</i>\n\n"));
+        assert!(out.contains("NOTE translated by hand"));
+    }
+
+    /// Ensure translated text with blank lines or arrows stays inside its cue.
+    #[test]
+    fn escapes_cue_payloads() {
+        let doc = parse(SAMPLE).unwrap();
+        let mut cues = doc.to_cues();
+        cues[0].text = vec!["Olá".into(), "".into(), "a --> b\n\ntchau".into()];
+        let out = format(&doc.with_cues(&cues));
+        assert!(out.contains("position:10%\nOlá\na --&gt; b\ntchau\n\n"));
+        let reparsed = parse(&out).unwrap();
+        assert_eq!(reparsed.cues().count(), 2);
+        assert_eq!(reparsed.cues().next().unwrap().text.len(), 3);
+    }
+
+    /// Ensure files without the signature are rejected.
+    #[test]
+    fn rejects_missing_signature() {
+        assert!(parse("1\n00:00:00,000 --> 00:00:01,000\nHi\n").is_err());
+    }
+}